@@ -1,18 +1,40 @@
-#[aoc_generator(day1)]
-pub fn day1_input(input: &str) -> Vec<Vec<u32>> {
-    input
-        .split("\n\n")
-        .map(|l| l.lines().map(|m| m.parse::<u32>().unwrap()).collect())
-        .collect()
-}
-#[aoc(day1, part1)]
-pub fn part1(input: &[Vec<u32>]) -> u32 {
-    input.iter().map(|elf| elf.iter().sum()).max().unwrap()
-}
-#[aoc(day1, part2)]
-pub fn part2(input: &[Vec<u32>]) -> u32 {
-    let mut calorie_counts: Vec<u32> = input.iter().map(|elf| elf.iter().sum()).collect();
-    calorie_counts.sort();
-    calorie_counts.reverse();
-    calorie_counts[0..3].iter().sum()
-}
+use crate::error::{ParseError, Source};
+
+#[aoc_generator(day1)]
+pub fn day1_input(input: &str) -> Result<Vec<Vec<u32>>, ParseError> {
+    let src = Source::new(1, input);
+    input
+        .split("\n\n")
+        .map(|elf| elf.lines().map(|m| src.parse(m, "calorie count")).collect())
+        .collect()
+}
+#[aoc(day1, part1)]
+pub fn part1(input: &[Vec<u32>]) -> u32 {
+    input.iter().map(|elf| elf.iter().sum()).max().unwrap()
+}
+#[aoc(day1, part2)]
+pub fn part2(input: &[Vec<u32>]) -> u32 {
+    let mut calorie_counts: Vec<u32> = input.iter().map(|elf| elf.iter().sum()).collect();
+    calorie_counts.sort();
+    calorie_counts.reverse();
+    calorie_counts[0..3].iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day1_input(&example(1)).unwrap();
+        assert_eq!(part1(&input), 24000);
+        assert_eq!(part2(&input), 45000);
+    }
+    #[test]
+    fn real_answers() {
+        let input = day1_input(&real_input(1)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(1, 1));
+        assert_eq!(part2(&input).to_string(), answer(1, 2));
+    }
+}
@@ -18,8 +18,31 @@ pub fn part1(input: &str) -> i32 {
     }
     signal_strength
 }
-#[aoc(day10, part2)]
-pub fn part2(input: &str) -> u32 {
+const SCREEN_WIDTH: usize = 40;
+// letters are 4 pixels wide with a blank column after each
+const GLYPH_WIDTH: usize = 5;
+// the AoC 4x6 letter font
+const GLYPHS: [(char, [&str; 6]); 17] = [
+    ('A', [" ## ", "#  #", "#  #", "####", "#  #", "#  #"]),
+    ('B', ["### ", "#  #", "### ", "#  #", "#  #", "### "]),
+    ('C', [" ## ", "#  #", "#   ", "#   ", "#  #", " ## "]),
+    ('E', ["####", "#   ", "### ", "#   ", "#   ", "####"]),
+    ('F', ["####", "#   ", "### ", "#   ", "#   ", "#   "]),
+    ('G', [" ## ", "#  #", "#   ", "# ##", "#  #", " ###"]),
+    ('H', ["#  #", "#  #", "####", "#  #", "#  #", "#  #"]),
+    ('I', [" ###", "  # ", "  # ", "  # ", "  # ", " ###"]),
+    ('J', ["  ##", "   #", "   #", "   #", "#  #", " ## "]),
+    ('K', ["#  #", "# # ", "##  ", "# # ", "# # ", "#  #"]),
+    ('L', ["#   ", "#   ", "#   ", "#   ", "#   ", "####"]),
+    ('O', [" ## ", "#  #", "#  #", "#  #", "#  #", " ## "]),
+    ('P', ["### ", "#  #", "#  #", "### ", "#   ", "#   "]),
+    ('R', ["### ", "#  #", "#  #", "### ", "# # ", "#  #"]),
+    ('S', [" ###", "#   ", "#   ", " ## ", "   #", "### "]),
+    ('U', ["#  #", "#  #", "#  #", "#  #", "#  #", " ## "]),
+    ('Z', ["####", "   #", "  # ", " #  ", "#   ", "####"]),
+];
+// draw the crt one line per row, # for lit pixels
+pub fn render(input: &str) -> String {
     let mut clock = 0;
     let mut x = 1;
     let mut text_buffer: Vec<char> = Vec::new();
@@ -43,7 +66,68 @@ pub fn part2(input: &str) -> u32 {
         }
     }
     text_buffer
-        .chunks(40)
-        .for_each(|c| println!("{}", c.iter().collect::<String>()));
-    0
+        .chunks(SCREEN_WIDTH)
+        .map(|c| c.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+// read the letters off a rendered screen, ? for anything not in the font
+pub fn ocr(screen: &str) -> String {
+    let rows: Vec<&str> = screen.lines().collect();
+    let width = rows.iter().map(|r| r.len()).max().unwrap_or(0);
+    (0..width)
+        .step_by(GLYPH_WIDTH)
+        .map(|col| {
+            let cell = |row: &str| row.get(col..col + 4).unwrap_or("").to_string();
+            GLYPHS
+                .iter()
+                .find(|(_, glyph)| {
+                    rows.len() == glyph.len() && rows.iter().zip(glyph).all(|(r, g)| cell(r) == *g)
+                })
+                .map_or('?', |&(c, _)| c)
+        })
+        .collect()
+}
+#[aoc(day10, part2)]
+pub fn part2(input: &str) -> String {
+    ocr(&render(input))
+}
+// the raw screen, for when the letters don't come out right
+#[aoc(day10, part2, raw)]
+pub fn part2_raw(input: &str) -> String {
+    format!("\n{}", render(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    // the example's CRT output isn't made of letters, so check the raw screen instead
+    #[test]
+    fn example_answers() {
+        let input = example(10);
+        assert_eq!(part1(&input), 13140);
+        let screen = render(&input);
+        assert_eq!(screen.lines().count(), 6);
+        assert!(screen.starts_with("##  ##  ##  ##  ##  ##  ##  ##  ##  ##  \n"));
+        assert!(screen.ends_with("#######       #######       #######     "));
+    }
+    #[test]
+    fn reads_letters() {
+        let glyph = |c| GLYPHS.iter().find(|g| g.0 == c).unwrap().1;
+        let blob = ["####"; 6];
+        let screen = (0..6)
+            .map(|row| format!("{} {} {} ", glyph('H')[row], blob[row], glyph('Z')[row]))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_eq!(ocr(&screen), "H?Z");
+        assert_eq!(ocr(""), "");
+    }
+    #[test]
+    fn real_answers() {
+        let input = real_input(10);
+        assert_eq!(part1(&input).to_string(), answer(10, 1));
+        assert_eq!(part2(&input), answer(10, 2));
+    }
 }
@@ -1,17 +1,18 @@
+use crate::error::{ParseError, Source};
 use itertools::Itertools;
 use std::collections::VecDeque;
-use std::str::{FromStr, Lines};
+use std::str::FromStr;
 #[derive(Debug, Clone)]
 struct Monkey {
-    items: VecDeque<u32>,
+    items: VecDeque<u64>,
     operation: Operation,
     test: Test,
     inspections: u64,
 }
 impl Monkey {
-    fn inspect(&mut self, divisor: Option<u32>) -> Option<(u32, usize)> {
+    fn inspect(&mut self, divisor: Option<u64>) -> Option<(u64, usize)> {
         // get next item, if there is any
-        let Some(mut curr) = self.items.pop_front() else {return None;};
+        let mut curr = self.items.pop_front()?;
         // apply operation
         curr = self.operation.apply(curr);
         self.inspections += 1;
@@ -28,12 +29,12 @@ impl Monkey {
 }
 #[derive(Debug, Clone)]
 enum Operation {
-    Add(u32),
-    Mult(u32),
+    Add(u64),
+    Mult(u64),
     Square(),
 }
 impl Operation {
-    fn apply(&self, old: u32) -> u32 {
+    fn apply(&self, old: u64) -> u64 {
         match self {
             Operation::Add(val) => old + val,
             Operation::Mult(val) => old * val,
@@ -43,33 +44,35 @@ impl Operation {
 }
 #[derive(Debug, Clone)]
 struct Test {
-    test_value: u32,
+    test_value: u64,
     if_true: usize,
     if_false: usize,
 }
 impl Test {
-    fn apply(&self, val: u32) -> usize {
-        if val % self.test_value == 0 {
+    fn apply(&self, val: u64) -> usize {
+        if val.is_multiple_of(self.test_value) {
             self.if_true
         } else {
             self.if_false
         }
     }
 }
-fn error() -> &'static str {
-    "Failed to parse monke"
-}
-impl FromStr for Monkey {
-    type Err = &'static str;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Monkey {
+    fn parse(src: &Source, s: &str) -> Result<Self, ParseError> {
         let mut lines = s.lines();
-        let mut next = { || lines.next().ok_or_else(error) };
+        let mut next = |expected| lines.next().ok_or_else(|| src.missing(expected));
 
-        next()?.chars().nth(7).unwrap().to_digit(10).unwrap();
-        let items = parse_items(next()?)?;
-        let op = parse_op(next()?)?;
-        let test = parse_test(lines);
+        let header = next("monkey header")?;
+        if !header.starts_with("Monkey ") {
+            return Err(src.error(header, "Monkey N:"));
+        }
+        let items = parse_items(src, next("starting items")?)?;
+        let op = parse_op(src, next("operation")?)?;
+        let test = Test {
+            test_value: parse_last(src, next("divisibility test")?)?,
+            if_true: parse_last(src, next("true branch")?)?,
+            if_false: parse_last(src, next("false branch")?)?,
+        };
         Ok(Monkey {
             items,
             operation: op,
@@ -78,43 +81,28 @@ impl FromStr for Monkey {
         })
     }
 }
-fn parse_test(lines: Lines) -> Test {
-    let (div, t, f) = lines
-        .map(|l| l.split(' ').last().unwrap())
-        .map(|num| num.parse().unwrap())
-        .collect_tuple()
-        .unwrap();
-    Test {
-        test_value: div as u32,
-        if_true: t,
-        if_false: f,
+// test lines all end in the number we care about
+fn parse_last<T: FromStr>(src: &Source, s: &str) -> Result<T, ParseError> {
+    src.parse(s.split(' ').next_back().unwrap(), "number")
+}
+fn parse_op(src: &Source, s: &str) -> Result<Operation, ParseError> {
+    let (_, r) = src.split_once(s, "old ")?;
+    let (l, r) = src.split_once(r, " ")?;
+    match (l, r) {
+        ("*", "old") => Ok(Operation::Square()),
+        ("*", val) => Ok(Operation::Mult(src.parse(val, "number or old")?)),
+        ("+", val) => Ok(Operation::Add(src.parse(val, "number")?)),
+        _ => Err(src.error(l, "* or +")),
     }
 }
-fn parse_op(s: &str) -> Result<Operation, &'static str> {
-    s.split_once("old ")
-        .and_then(|(_, r)| r.split_once(' '))
-        .and_then(|(l, r)| {
-            let Some(val) = r.parse::<u32>().ok() else {
-                return Some(Operation::Square());
-            };
-            match l {
-                "*" => Some(Operation::Mult(val)),
-                "+" => Some(Operation::Add(val)),
-                _ => unreachable!(),
-            }
-        })
-        .ok_or_else(error)
-}
-fn parse_items(s: &str) -> Result<VecDeque<u32>, &'static str> {
-    s.split_once(':')
-        .map(|(_, items)| {
-            items
-                .trim()
-                .split(',')
-                .filter_map(|r| r.trim().parse::<u32>().ok())
-                .collect::<VecDeque<u32>>()
-        })
-        .ok_or_else(error)
+fn parse_items(src: &Source, s: &str) -> Result<VecDeque<u64>, ParseError> {
+    let (_, items) = src.split_once(s, ":")?;
+    items
+        .split(',')
+        .map(str::trim)
+        .filter(|r| !r.is_empty())
+        .map(|r| src.parse(r, "worry level"))
+        .collect()
 }
 fn monkey_business(monkeys: &[Monkey]) -> u64 {
     monkeys
@@ -125,15 +113,12 @@ fn monkey_business(monkeys: &[Monkey]) -> u64 {
         .product()
 }
 fn print_monkeys(monkeys: &[Monkey]) {
-    for i in 0..monkeys.len() {
-        println!(
-            "Monkey {i} inspected items {} times",
-            monkeys[i].inspections
-        );
+    for (i, monkey) in monkeys.iter().enumerate() {
+        println!("Monkey {i} inspected items {} times", monkey.inspections);
     }
     println!()
 }
-fn solve(monkeys: &mut [Monkey], rounds: u32, divisor: Option<u32>) -> u64 {
+fn solve(monkeys: &mut [Monkey], rounds: u32, divisor: Option<u64>) -> u64 {
     println!("{monkeys:#?}");
     for i in 0..rounds {
         for i in 0..monkeys.len() {
@@ -149,10 +134,11 @@ fn solve(monkeys: &mut [Monkey], rounds: u32, divisor: Option<u32>) -> u64 {
     monkey_business(monkeys)
 }
 #[aoc_generator(day11)]
-fn parse_monkeys(input: &str) -> Vec<Monkey> {
+fn parse_monkeys(input: &str) -> Result<Vec<Monkey>, ParseError> {
+    let src = Source::new(11, input);
     input
         .split("\n\n")
-        .map(|monke| monke.parse().unwrap())
+        .map(|monke| Monkey::parse(&src, monke))
         .collect()
 }
 #[aoc(day11, part1)]
@@ -161,6 +147,25 @@ fn part1(input: &[Monkey]) -> u64 {
 }
 #[aoc(day11, part2)]
 fn part2(input: &[Monkey]) -> u64 {
-    let divisor: u32 = input.iter().map(|m| m.test.test_value).product();
+    let divisor: u64 = input.iter().map(|m| m.test.test_value).product();
     solve(&mut input.to_vec(), 10_000, Some(divisor))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = parse_monkeys(&example(11)).unwrap();
+        assert_eq!(part1(&input), 10605);
+        assert_eq!(part2(&input), 2713310158);
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse_monkeys(&real_input(11)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(11, 1));
+        assert_eq!(part2(&input).to_string(), answer(11, 2));
+    }
+}
@@ -1,69 +1,177 @@
+use crate::error::{ParseError, Source};
+use crate::grid::{Grid, Pos};
+use crate::search::{self, Graph, Path, SearchTree};
 use itertools::Itertools;
-use std::collections::VecDeque;
 #[derive(Debug, Clone)]
-struct Grid {
-    start: Vec<(usize, usize)>,
-    end: (usize, usize),
-    grid: Vec<Vec<u8>>,
+struct HeightMap {
+    start: Pos,
+    end: Pos,
+    grid: Grid<u8>,
 }
 #[aoc_generator(day12)]
-fn day12_input(input: &str) -> Grid {
-    let mut grid: Vec<Vec<u8>> = input
-        .lines()
-        .map(|l| l.as_bytes().to_vec())
-        .collect::<Vec<_>>();
+fn day12_input(input: &str) -> Result<HeightMap, ParseError> {
+    let src = Source::new(12, input);
+    let mut grid = Grid::parse(&src, input, "height a-z, S or E", |c| {
+        (c.is_ascii_lowercase() || c == 'S' || c == 'E').then_some(c as u8)
+    })?;
 
-    let (sx, sy) = (0..grid.len())
-        .cartesian_product(0..grid[0].len())
-        .find(|&(x, y)| grid[x][y] == b'S')
-        .unwrap();
-    let (gx, gy) = (0..grid.len())
-        .cartesian_product(0..grid[0].len())
-        .find(|&(x, y)| grid[x][y] == b'E')
-        .unwrap();
-    grid[sx][sy] = b'a';
-    grid[gx][gy] = b'z';
-    Grid {
-        start: Vec::from([(sx, sy)]),
-        end: (gx, gy),
-        grid,
+    let start = grid
+        .position(|&c| c == b'S')
+        .ok_or_else(|| src.missing("start S"))?;
+    let end = grid
+        .position(|&c| c == b'E')
+        .ok_or_else(|| src.missing("summit E"))?;
+    grid[start] = b'a';
+    grid[end] = b'z';
+    Ok(HeightMap { start, end, grid })
+}
+
+impl Graph for HeightMap {
+    type Node = Pos;
+
+    // we can climb at most one step up, but drop any distance
+    fn neighbors(&self, &pos: &Pos) -> impl Iterator<Item = (Pos, usize)> {
+        let grid = &self.grid;
+        grid.neighbors4(pos)
+            .filter(move |&next| grid[pos] + 1 >= grid[next])
+            .map(|next| (next, 1))
     }
 }
+// quickest route up to the summit from any of `starts`
+fn climb(input: &HeightMap, starts: impl IntoIterator<Item = Pos>) -> Path<Pos> {
+    search::bfs(input, starts, |&pos| pos == input.end).expect("no way up to the summit")
+}
+// the same map walked backwards from the summit, so each step can drop
+// at most one but climb any distance
+struct Descent<'a>(&'a HeightMap);
+impl Graph for Descent<'_> {
+    type Node = Pos;
 
-fn bfs(forest: &Grid) -> Option<usize> {
-    let grid = &forest.grid;
-    let mut mark = vec![vec![false; grid[0].len()]; grid.len()];
-    let mut queue = forest
-        .start
+    fn neighbors(&self, &pos: &Pos) -> impl Iterator<Item = (Pos, usize)> {
+        let grid = &self.0.grid;
+        grid.neighbors4(pos)
+            .filter(move |&prev| grid[prev] + 1 >= grid[pos])
+            .map(|prev| (prev, 1))
+    }
+}
+// steps from every cell up to the summit, in one search from the top
+fn distances_to_summit(input: &HeightMap) -> SearchTree<Pos> {
+    search::bfs_all(&Descent(input), [input.end])
+}
+// the best hike starts from whichever a is closest to the summit
+fn best_hike(input: &HeightMap) -> Path<Pos> {
+    let field = distances_to_summit(input);
+    let (_, start) = input
+        .grid
         .iter()
-        .map(|&(x, y)| (x, y, 0))
-        .collect::<VecDeque<_>>();
-    while let Some((x, y, len)) = queue.pop_front() {
-        if (x, y) == forest.end {
-            return Some(len);
-        }
-        for (dx, dy) in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
-            let (x2, y2) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
-            // bounds checking
-            let Some(&next) = grid.get(x2).and_then(|row| row.get(y2)) else {continue};
-            if grid[x][y] + 1 >= next && !mark[x2][y2] {
-                mark[x2][y2] = true;
-                queue.push_back((x2, y2, len + 1));
-            }
-        }
+        .filter(|&(_, &h)| h == b'a')
+        .filter_map(|(pos, _)| Some((field.distance(&pos)?, pos)))
+        .min()
+        .expect("no way up to the summit");
+    // the search ran downhill, so turn the route round
+    let mut hike = field.path_to(&start).unwrap();
+    hike.nodes.reverse();
+    hike
+}
+// the distances to the summit as a plain PGM image, brighter is closer and
+// black is anywhere the summit can't be reached from
+fn heat_map(input: &HeightMap) -> String {
+    let field = distances_to_summit(input);
+    let grid = &input.grid;
+    let max = field.distances().map(|(_, d)| d).max().unwrap_or(0) + 1;
+    let mut image = format!("P2\n{} {}\n{max}\n", grid.width(), grid.height());
+    for row in 0..grid.height() {
+        let mut pixels =
+            (0..grid.width()).map(|col| field.distance(&(row, col)).map_or(0, |d| max - d));
+        image.push_str(&pixels.join(" "));
+        image.push('\n');
+    }
+    image
+}
+// the height map with the route drawn over it like on the puzzle page,
+// each step an arrow pointing at the next one
+fn render(input: &HeightMap, route: &Path<Pos>) -> String {
+    let mut map = input.grid.map(|&h| h as char);
+    for (&(r, c), &next) in route.nodes.iter().tuple_windows() {
+        map[(r, c)] = match next {
+            (nr, _) if nr < r => '^',
+            (nr, _) if nr > r => 'v',
+            (_, nc) if nc > c => '>',
+            _ => '<',
+        };
     }
-    None
+    map[input.end] = 'E';
+    let rows = map.rows().map(|row| row.iter().collect::<String>());
+    rows.collect::<Vec<_>>().join("\n")
 }
 #[aoc(day12, part1)]
-fn part1(input: &Grid) -> usize {
-    bfs(input).unwrap()
+fn part1(input: &HeightMap) -> usize {
+    climb(input, [input.start]).cost
+}
+#[aoc(day12, part1, route)]
+fn part1_route(input: &HeightMap) -> String {
+    format!("\n{}", render(input, &climb(input, [input.start])))
 }
 #[aoc(day12, part2)]
-fn part2(input: &Grid) -> usize {
-    let mut input_2 = input.clone();
-    input_2.start = (0..input.grid.len())
-        .cartesian_product(0..input.grid[0].len())
-        .filter(|&(x, y)| input.grid[x][y] == b'a')
-        .collect();
-    bfs(&input_2).unwrap()
+fn part2(input: &HeightMap) -> usize {
+    best_hike(input).cost
+}
+// which a the best hike starts from, and the route it takes
+#[aoc(day12, part2, route)]
+fn part2_route(input: &HeightMap) -> String {
+    let hike = best_hike(input);
+    let (row, col) = hike.start();
+    format!("from row {row}, col {col}\n{}", render(input, &hike))
+}
+#[aoc(day12, part2, heat_map)]
+fn part2_heat_map(input: &HeightMap) -> String {
+    format!("\n{}", heat_map(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day12_input(&example(12)).unwrap();
+        assert_eq!(part1(&input), 31);
+        assert_eq!(part2(&input), 29);
+    }
+    #[test]
+    fn route_is_drawn_with_arrows() {
+        let input = day12_input(&example(12)).unwrap();
+        let route = climb(&input, [input.start]);
+        assert_eq!(route.nodes.len(), 32);
+        assert_eq!((*route.start(), *route.end()), ((0, 0), (2, 5)));
+        let map = render(&input, &route);
+        assert_eq!(map.matches(['^', '>', 'v', '<']).count(), 31);
+        assert_eq!(map.lines().nth(2).unwrap().find('E'), Some(5));
+        let hike = best_hike(&input);
+        assert_eq!(input.grid[*hike.start()], b'a');
+        assert_eq!((hike.nodes.len(), *hike.end()), (30, input.end));
+    }
+    #[test]
+    fn distance_field_from_the_summit() {
+        let input = day12_input(&example(12)).unwrap();
+        let field = distances_to_summit(&input);
+        assert_eq!(field.distance(&input.start), Some(31));
+        assert_eq!(field.distance(&input.end), Some(0));
+        let image = heat_map(&input);
+        let mut lines = image.lines();
+        assert_eq!(lines.next(), Some("P2"));
+        assert_eq!(lines.next(), Some("8 5"));
+        let max: usize = lines.next().unwrap().parse().unwrap();
+        assert_eq!(
+            lines.nth(2).unwrap().split(' ').nth(5),
+            Some(&*max.to_string())
+        );
+    }
+    #[test]
+    fn real_answers() {
+        let input = day12_input(&real_input(12)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(12, 1));
+        assert_eq!(part2(&input).to_string(), answer(12, 2));
+    }
 }
@@ -1,90 +1,112 @@
-use crate::day13::Packet::*;
-use std::cmp::Ordering;
-#[derive(Eq, PartialEq, Clone)]
-enum Packet {
-    Node(u8),
-    List(Vec<Packet>),
-}
-impl PartialOrd for Packet {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
-impl Ord for Packet {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            // simply compare ints
-            (Node(x), Node(y)) => x.cmp(y),
-            // compare each element or lengths
-            (List(a), List(b)) => match a.iter().cmp(b) {
-                Ordering::Equal => a.len().cmp(&b.len()),
-                ord => ord,
-            },
-            // turn node to list
-            (List(_), Node(x)) => self.cmp(&List(vec![Node(*x)])),
-            // just use above pattern
-            (Node(_), List(_)) => other.cmp(self).reverse(),
-        }
-    }
-}
-impl Packet {
-    fn from_str(s: &str) -> Packet {
-        if s.starts_with('[') {
-            let s = &s[1..s.len() - 1];
-            // empty list
-            let mut inner = Vec::new();
-            let (mut depth, mut substr_start) = (0, 0);
-            if s.is_empty() {
-                return List(vec![]);
-            }
-            for (i, c) in s.chars().enumerate() {
-                match c {
-                    '[' => depth += 1,
-                    ']' => depth -= 1,
-                    ',' if depth == 0 => {
-                        inner.push(Self::from_str(&s[substr_start..i]));
-                        substr_start = i + 1;
-                    }
-                    _ => {}
-                }
-            }
-            inner.push(Self::from_str(&s[substr_start..]));
-            List(inner)
-        } else {
-            println!("Parsing {s}");
-            Node(s.parse().unwrap())
-        }
-    }
-}
-#[aoc_generator(day13)]
-fn day13_input(input: &str) -> Vec<Packet> {
-    input
-        .split('\n')
-        .filter(|s| !s.is_empty())
-        .map(Packet::from_str)
-        .collect()
-}
-#[aoc(day13, part1)]
-fn part1(input: &[Packet]) -> usize {
-    input.chunks(2).enumerate().fold(
-        0,
-        |acc, (i, pair)| {
-            if pair[0] < pair[1] {
-                acc + i + 1
-            } else {
-                acc
-            }
-        },
-    )
-}
-#[aoc(day13, part2)]
-fn part2(input: &[Packet]) -> usize {
-    let mut input = input.to_vec();
-    let div1 = Packet::from_str("[[2]]");
-    let div2 = Packet::from_str("[[6]]");
-    input.push(div1.clone());
-    input.push(div2.clone());
-    input.sort_unstable();
-    (input.iter().position(|p| *p == div1).unwrap() + 1)
-        * (input.iter().position(|p| *p == div2).unwrap() + 1)
-}
+use crate::day13::Packet::*;
+use crate::error::{ParseError, Source};
+use std::cmp::Ordering;
+#[derive(Eq, PartialEq, Clone)]
+enum Packet {
+    Node(u8),
+    List(Vec<Packet>),
+}
+impl PartialOrd for Packet {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Packet {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            // simply compare ints
+            (Node(x), Node(y)) => x.cmp(y),
+            // compare each element or lengths
+            (List(a), List(b)) => match a.iter().cmp(b) {
+                Ordering::Equal => a.len().cmp(&b.len()),
+                ord => ord,
+            },
+            // turn node to list
+            (List(_), Node(x)) => self.cmp(&List(vec![Node(*x)])),
+            // just use above pattern
+            (Node(_), List(_)) => other.cmp(self).reverse(),
+        }
+    }
+}
+impl Packet {
+    fn parse(src: &Source, s: &str) -> Result<Packet, ParseError> {
+        if let Some(rest) = s.strip_prefix('[') {
+            let s = rest
+                .strip_suffix(']')
+                .ok_or_else(|| src.error(&rest[rest.len()..], "]"))?;
+            // empty list
+            let mut inner = Vec::new();
+            let (mut depth, mut substr_start) = (0, 0);
+            if s.is_empty() {
+                return Ok(List(vec![]));
+            }
+            for (i, c) in s.char_indices() {
+                match c {
+                    '[' => depth += 1,
+                    ']' => depth -= 1,
+                    ',' if depth == 0 => {
+                        inner.push(Self::parse(src, &s[substr_start..i])?);
+                        substr_start = i + 1;
+                    }
+                    _ => {}
+                }
+            }
+            inner.push(Self::parse(src, &s[substr_start..])?);
+            Ok(List(inner))
+        } else {
+            Ok(Node(src.parse(s, "integer or list")?))
+        }
+    }
+}
+#[aoc_generator(day13)]
+fn day13_input(input: &str) -> Result<Vec<Packet>, ParseError> {
+    let src = Source::new(13, input);
+    input
+        .lines()
+        .filter(|s| !s.is_empty())
+        .map(|s| Packet::parse(&src, s))
+        .collect()
+}
+#[aoc(day13, part1)]
+fn part1(input: &[Packet]) -> usize {
+    input.chunks(2).enumerate().fold(
+        0,
+        |acc, (i, pair)| {
+            if pair[0] < pair[1] {
+                acc + i + 1
+            } else {
+                acc
+            }
+        },
+    )
+}
+#[aoc(day13, part2)]
+fn part2(input: &[Packet]) -> usize {
+    let mut input = input.to_vec();
+    let div1 = List(vec![List(vec![Node(2)])]);
+    let div2 = List(vec![List(vec![Node(6)])]);
+    input.push(div1.clone());
+    input.push(div2.clone());
+    input.sort_unstable();
+    (input.iter().position(|p| *p == div1).unwrap() + 1)
+        * (input.iter().position(|p| *p == div2).unwrap() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day13_input(&example(13)).unwrap();
+        assert_eq!(part1(&input), 13);
+        assert_eq!(part2(&input), 140);
+    }
+    #[test]
+    fn real_answers() {
+        let input = day13_input(&real_input(13)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(13, 1));
+        assert_eq!(part2(&input).to_string(), answer(13, 2));
+    }
+}
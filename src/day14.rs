@@ -1,42 +1,50 @@
+use crate::error::{ParseError, Source};
+use crate::grid::Grid;
 use itertools::Itertools;
 use std::cmp::{max, min};
 const STARTX: usize = 500;
 const STARTY: usize = 0;
 const DIMX: usize = 1000;
 const DIMY: usize = 1000;
-type Grid = Box<[Vec<bool>]>;
+// indexed (y, x), true where there is rock or sand
+type Cave = Grid<bool>;
 #[aoc_generator(day14)]
-fn parse_input(input: &str) -> (Grid, usize) {
-    let mut grid = vec![vec![false; DIMX]; DIMY].into_boxed_slice();
+fn parse_input(input: &str) -> Result<(Cave, usize), ParseError> {
+    let src = Source::new(14, input);
+    let mut grid = Grid::new(DIMX, DIMY, false);
     let mut floor: usize = 0;
     for line in input.lines() {
         let pairs: Vec<(usize, usize)> = line
             .split(" -> ")
             .map(|s| {
-                s.split_once(',')
-                    .map(|(x, y)| (x.parse::<usize>().unwrap(), y.parse::<usize>().unwrap()))
-                    .unwrap()
+                let (x, y) = src.split_once(s, ",")?;
+                // leave room under the lowest rock for the part 2 floor
+                match (src.parse(x, "x coordinate")?, src.parse(y, "y coordinate")?) {
+                    (x, _) if x >= DIMX => Err(src.error(s, &format!("x below {DIMX}"))),
+                    (_, y) if y + 2 >= DIMY => Err(src.error(s, &format!("y below {}", DIMY - 2))),
+                    pos => Ok(pos),
+                }
             })
-            .collect();
+            .collect::<Result<_, ParseError>>()?;
         for (&(sx, sy), &(ex, ey)) in pairs.iter().tuple_windows() {
             // vertical line
             if sx == ex {
                 // update floor
                 let maxy = max(sy, ey);
                 floor = max(floor, maxy);
-                (min(sy, ey)..=maxy).for_each(|i| grid[sx][i] = true);
+                (min(sy, ey)..=maxy).for_each(|i| grid[(i, sx)] = true);
             } else {
                 // update floor
                 floor = max(floor, sy);
-                (min(sx, ex)..=max(sx, ex)).for_each(|i| grid[i][sy] = true);
+                (min(sx, ex)..=max(sx, ex)).for_each(|i| grid[(sy, i)] = true);
             }
         }
     }
     floor += 2;
     println!("Floor is {floor}");
-    (grid, floor)
+    Ok((grid, floor))
 }
-fn drop_sand(grid: &mut Grid) -> usize {
+fn drop_sand(grid: &mut Cave) -> usize {
     let mut x = STARTX;
     let mut y = STARTY;
     let mut count = 0;
@@ -46,16 +54,16 @@ fn drop_sand(grid: &mut Grid) -> usize {
             break;
         }
         // check down
-        if !grid[x][y + 1] {
+        if !grid[(y + 1, x)] {
             y += 1;
-        } else if x > 0 && !grid[x - 1][y + 1] {
+        } else if x > 0 && !grid[(y + 1, x - 1)] {
             x -= 1;
             y += 1;
-        } else if x < DIMX - 1 && !grid[x + 1][y + 1] {
+        } else if x < DIMX - 1 && !grid[(y + 1, x + 1)] {
             x += 1;
             y += 1;
         } else {
-            grid[x][y] = true;
+            grid[(y, x)] = true;
             count += 1;
             y = STARTY;
             x = STARTX;
@@ -64,32 +72,32 @@ fn drop_sand(grid: &mut Grid) -> usize {
     count
 }
 #[aoc(day14, part1)]
-fn part1(input: &(Grid, usize)) -> usize {
+fn part1(input: &(Cave, usize)) -> usize {
     let mut grid = input.0.clone();
     drop_sand(&mut grid)
 }
 #[aoc(day14, part2)]
-fn part2(input: &(Grid, usize)) -> usize {
+fn part2(input: &(Cave, usize)) -> usize {
     let mut sand = 0;
     let mut grid = input.0.clone();
     let floor = input.1;
-    grid.iter_mut().for_each(|c| c[floor] = true);
+    (0..DIMX).for_each(|x| grid[(floor, x)] = true);
     'sand: loop {
         let (mut x, mut y): (usize, usize) = (500, 0);
         'fall: loop {
-            if !grid[x][y + 1] {
+            if !grid[(y + 1, x)] {
                 y += 1;
-            } else if !grid[x - 1][y + 1] {
+            } else if !grid[(y + 1, x - 1)] {
                 x -= 1;
                 y += 1;
-            } else if !grid[x + 1][y + 1] {
+            } else if !grid[(y + 1, x + 1)] {
                 x += 1;
                 y += 1;
             } else if (x, y) == (500, 0) {
                 sand += 1;
                 break 'sand;
             } else {
-                grid[x][y] = true;
+                grid[(y, x)] = true;
                 break 'fall;
             }
         }
@@ -97,3 +105,22 @@ fn part2(input: &(Grid, usize)) -> usize {
     }
     sand
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = parse_input(&example(14)).unwrap();
+        assert_eq!(part1(&input), 24);
+        assert_eq!(part2(&input), 93);
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse_input(&real_input(14)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(14, 1));
+        assert_eq!(part2(&input).to_string(), answer(14, 2));
+    }
+}
@@ -1,9 +1,41 @@
+use crate::error::{ParseError, Source};
 use itertools::Itertools;
 use regex::Regex;
 use std::cmp::max;
 use std::ops::Range;
 
 type Input = (Vec<Sensor>, Vec<Beacon>);
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Day15Params {
+    // row to count for part 1
+    pub row: isize,
+    // distress beacon has both coordinates in 0..=max_coord
+    pub max_coord: isize,
+}
+impl Default for Day15Params {
+    fn default() -> Self {
+        Day15Params {
+            row: 2_000_000,
+            max_coord: 4_000_000,
+        }
+    }
+}
+impl Day15Params {
+    pub const EXAMPLE: Self = Day15Params {
+        row: 10,
+        max_coord: 20,
+    };
+    // the example is tiny, real inputs have sensors millions of units out.
+    // only a guess, anything else small needs its params given to `scan_with`
+    fn detect(input: &Input) -> Self {
+        let small = input.0.iter().all(|s| s.x.abs() <= 100 && s.y.abs() <= 100);
+        if small {
+            Self::EXAMPLE
+        } else {
+            Self::default()
+        }
+    }
+}
 struct Beacon {
     x: isize,
     y: isize,
@@ -15,7 +47,7 @@ struct Sensor {
 }
 impl Sensor {
     fn range_at_row(&self, row: isize) -> Option<Range<isize>> {
-        let reach = (self.range - row.abs_diff(self.y)) as isize;
+        let reach = self.range as isize - row.abs_diff(self.y) as isize;
         if reach >= 0 {
             let length = 2 * reach + 1;
             let start = self.x - reach;
@@ -40,36 +72,29 @@ fn manhattan(from: (isize, isize), to: (isize, isize)) -> usize {
     from.0.abs_diff(to.0) + from.1.abs_diff(to.1)
 }
 #[aoc_generator(day15)]
-fn parse_input(input: &str) -> Input {
+fn parse_input(input: &str) -> Result<Input, ParseError> {
+    let src = Source::new(15, input);
     let re = Regex::new(r".*x=(-?\d+), y=(-?\d+).*x=(-?\d+), y=(-?\d+)").unwrap();
 
-    input
+    let pairs = input
         .lines()
-        .map(|l| re.captures(l))
-        .map(|cap| {
-            let cap = cap.unwrap();
-            (
-                (
-                    cap.get(1).unwrap().as_str().parse::<isize>().unwrap(),
-                    cap.get(2).unwrap().as_str().parse::<isize>().unwrap(),
-                ),
-                (
-                    cap.get(3).unwrap().as_str().parse::<isize>().unwrap(),
-                    cap.get(4).unwrap().as_str().parse::<isize>().unwrap(),
-                ),
-            )
-        })
-        .map(|(s, b)| {
-            (
+        .map(|l| {
+            let cap = re
+                .captures(l)
+                .ok_or_else(|| src.error(l, "Sensor at x=N, y=N: closest beacon is at x=N, y=N"))?;
+            let num = |i| src.parse::<isize>(cap.get(i).unwrap().as_str(), "coordinate");
+            let (s, b) = ((num(1)?, num(2)?), (num(3)?, num(4)?));
+            Ok((
                 Sensor {
                     x: s.0,
                     y: s.1,
                     range: manhattan(s, b),
                 },
                 Beacon { x: b.0, y: b.1 },
-            )
+            ))
         })
-        .unzip()
+        .collect::<Result<Vec<_>, ParseError>>()?;
+    Ok(pairs.into_iter().unzip())
 }
 // make non-overlapping ranges on row (where beacons can't be)
 fn ranges_at_row(sensors: &[Sensor], row: isize) -> Vec<Range<isize>> {
@@ -101,9 +126,7 @@ fn ranges_at_row(sensors: &[Sensor], row: isize) -> Vec<Range<isize>> {
         None => Vec::new(),
     }
 }
-#[aoc(day15, part1)]
-fn part1(input: &Input) -> usize {
-    let row = 2_000_000;
+fn no_beacon_count(input: &Input, row: isize) -> usize {
     let beacons_at_row = input
         .1
         .iter()
@@ -118,43 +141,114 @@ fn part1(input: &Input) -> usize {
         .sum::<usize>()
         - beacons_at_row
 }
-fn find_free(points: &mut [((isize, isize), &Sensor)], row: isize) -> Option<isize> {
+// the first x in 0..=max_coord on row that no sensor covers
+fn find_free(
+    points: &mut [(Option<(isize, isize)>, &Sensor)],
+    row: isize,
+    max_coord: isize,
+) -> Option<isize> {
     points.iter_mut().for_each(|p| {
-        p.0 =
-            p.1.range_at_row(row)
-                .map(|r| (r.start, r.end - 1))
-                .unwrap_or((0, 0));
+        p.0 = p.1.range_at_row(row).map(|r| (r.start, r.end - 1));
     });
 
-    points.sort_unstable_by(|a, b| a.0 .0.cmp(&b.0 .0));
-    let mut max_val: isize = 0;
-    for ((start, end), _) in &*points {
-        let start = *start;
-        let end = *end;
-        if (start, end) == (0, 0) {
-            continue;
-        }
-        if start > max_val {
-            return Some(start - 1);
+    points.sort_unstable_by_key(|a| a.0);
+    // everything from 0 up to here is covered
+    let mut covered: isize = -1;
+    for (start, end) in points.iter().filter_map(|p| p.0) {
+        if start > covered + 1 {
+            return Some(covered + 1);
         }
-        if end > 4_000_000 {
+        covered = max(covered, end);
+        if covered >= max_coord {
             return None;
         }
-        max_val = max(max_val, end);
     }
-    None
+    Some(covered + 1)
 }
-#[aoc(day15, part2)]
-fn part2(input: &Input) -> i64 {
+fn tuning_frequency(input: &Input, max_coord: isize) -> i64 {
     let sensors = &input.0;
-    let mut ranges: Vec<_> = vec![(0isize, 0isize); sensors.len()]
-        .into_iter()
-        .zip(sensors)
-        .collect();
-    for y in 0..=4_000_000 {
-        if let Some(avail) = find_free(&mut ranges, y) {
+    let mut ranges: Vec<_> = vec![None; sensors.len()].into_iter().zip(sensors).collect();
+    for y in 0..=max_coord {
+        if let Some(avail) = find_free(&mut ranges, y, max_coord) {
             return avail as i64 * 4_000_000 + y as i64;
         }
     }
     0
 }
+// both answers for a scan with its own row and search area, rather than
+// whichever of the puzzle's two the runner's parts guess at
+pub fn scan_with(input: &str, params: Day15Params) -> Result<(usize, i64), ParseError> {
+    let input = parse_input(input)?;
+    Ok((
+        no_beacon_count(&input, params.row),
+        tuning_frequency(&input, params.max_coord),
+    ))
+}
+#[aoc(day15, part1)]
+fn part1(input: &Input) -> usize {
+    no_beacon_count(input, Day15Params::detect(input).row)
+}
+#[aoc(day15, part2)]
+fn part2(input: &Input) -> i64 {
+    tuning_frequency(input, Day15Params::detect(input).max_coord)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn detects_example_params() {
+        let input = parse_input(&example(15)).unwrap();
+        assert_eq!(Day15Params::detect(&input), Day15Params::EXAMPLE);
+    }
+    #[test]
+    fn explicit_params() {
+        let example = example(15);
+        let params = Day15Params::EXAMPLE;
+        assert_eq!(scan_with(&example, params).unwrap(), (26, 56000011));
+        // small coordinates, but not the example's row or search area. up to
+        // 21 the gap at 14,11 is still the only one, below 14 there's none
+        let params = Day15Params {
+            row: 11,
+            max_coord: 21,
+        };
+        assert_eq!(scan_with(&example, params).unwrap(), (28, 56000011));
+        let params = Day15Params {
+            row: 11,
+            max_coord: 13,
+        };
+        assert_eq!(scan_with(&example, params).unwrap().1, 0);
+        // ranges that just touch leave no gap on row 0, the gap at 2,1 is
+        // the only one
+        let touching = "Sensor at x=0, y=0: closest beacon is at x=1, y=0
+Sensor at x=3, y=0: closest beacon is at x=4, y=0
+Sensor at x=1, y=3: closest beacon is at x=1, y=5
+Sensor at x=4, y=4: closest beacon is at x=4, y=7";
+        let params = Day15Params {
+            row: 0,
+            max_coord: 4,
+        };
+        assert_eq!(scan_with(touching, params).unwrap(), (4, 8_000_001));
+        // and one past the last range on row 0 is free
+        let params = Day15Params {
+            row: 0,
+            max_coord: 5,
+        };
+        assert_eq!(scan_with(touching, params).unwrap().1, 20_000_000);
+        assert!(scan_with("Sensor at x=1", params).is_err());
+    }
+    #[test]
+    fn example_answers() {
+        let input = parse_input(&example(15)).unwrap();
+        assert_eq!(part1(&input), 26);
+        assert_eq!(part2(&input), 56000011);
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse_input(&real_input(15)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(15, 1));
+        assert_eq!(part2(&input).to_string(), answer(15, 2));
+    }
+}
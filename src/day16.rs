@@ -1,14 +1,41 @@
-use std::collections::{HashMap, HashSet};
+use crate::error::{ParseError, Source};
+use crate::search::{self, Graph};
+use fxhash::FxHashMap;
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::iter;
+use std::ops::Index;
 
-const MAX_ROOMS: usize = 60;
-type DistanceMatrix = [[usize; MAX_ROOMS]; MAX_ROOMS];
+// a set of useful valves has to fit in a u64
+const MAX_USEFUL: usize = 64;
+// sharing valves out between agents looks at every set of them, and every
+// way of splitting each set, so that's 2^n memory and 3^n time
+const MAX_SHARED: usize = 18;
+// too many valves with a flow rate to share out between several agents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TooManyValves {
+    pub useful: usize,
+    pub agents: usize,
+}
+impl fmt::Display for TooManyValves {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} valves with a flow rate is too many to share between {} agents, at most {MAX_SHARED}",
+            self.useful, self.agents
+        )
+    }
+}
+impl Error for TooManyValves {}
 #[derive(Debug, Default, Eq, PartialEq, Hash, Clone)]
 struct Valve {
     flow_rate: usize,
     neighbors: Vec<usize>,
 }
 #[derive(Debug)]
-struct Cave {
+pub struct Cave {
     start_idx: usize,
     valves: Vec<Valve>,
     valve_map: HashMap<String, usize>,
@@ -17,196 +44,645 @@ impl Cave {
     fn new() -> Self {
         Cave {
             start_idx: usize::MAX,
-            valves: Vec::with_capacity(MAX_ROOMS),
+            valves: Vec::new(),
             valve_map: HashMap::new(),
         }
     }
-    // bfs type thing to calculate all distances
+    fn name(&self, idx: usize) -> &str {
+        self.valve_map.iter().find(|(_, &i)| i == idx).unwrap().0
+    }
+    // the valves with any flow, the dummy at 0 never gets opened
+    fn useful_valves(&self) -> Vec<usize> {
+        (1..self.valves.len())
+            .filter(|&i| self.valves[i].flow_rate > 0)
+            .collect()
+    }
+    // steps between every pair of valves worth visiting
     fn calc_distances(&self) -> DistanceMatrix {
-        let mut matrix = [[usize::MAX; MAX_ROOMS]; MAX_ROOMS];
-        let mut seen = HashSet::new();
-
-        for (i, _) in self
-            .valves
-            .iter()
-            .enumerate()
-            .filter(|&(i, r)| r.flow_rate > 0 || i == self.start_idx)
-        {
-            matrix[i][i] = 0;
-
-            let mut current = HashSet::new();
-            current.insert(i);
-            let mut next = HashSet::new();
-            let mut dist = 0;
-
-            while !current.is_empty() {
-                dist += 1;
-                for pos in &current {
-                    for new_valve in &self.valves[*pos].neighbors {
-                        if !seen.contains(&(i, *new_valve)) {
-                            next.insert(*new_valve);
-                            matrix[i][*new_valve] = dist;
-                            seen.insert((i, *new_valve));
-                        }
-                    }
+        let mut valves = self.useful_valves();
+        valves.push(self.start_idx);
+        let len = valves.len();
+        let mut steps = vec![usize::MAX; len * len];
+        for (i, &from) in valves.iter().enumerate() {
+            let tree = search::bfs_all(self, [from]);
+            for (j, to) in valves.iter().enumerate() {
+                if let Some(dist) = tree.distance(to) {
+                    steps[i * len + j] = dist;
                 }
-                current.clear();
-                current.extend(next.drain());
             }
         }
-        matrix
+        DistanceMatrix { valves, steps }
+    }
+}
+// steps between the useful valves, renumbered 0.. so a set of them fits in
+// a u64 bitmask, with the start tacked on as the last one
+#[derive(Debug, Clone)]
+struct DistanceMatrix {
+    // where each renumbered valve is in the cave
+    valves: Vec<usize>,
+    steps: Vec<usize>,
+}
+impl DistanceMatrix {
+    fn start(&self) -> usize {
+        self.valves.len() - 1
+    }
+    // how many valves are worth opening
+    fn useful(&self) -> usize {
+        self.valves.len() - 1
+    }
+    fn flow_rate(&self, cave: &Cave, valve: usize) -> usize {
+        cave.valves[self.valves[valve]].flow_rate
+    }
+}
+impl Index<(usize, usize)> for DistanceMatrix {
+    type Output = usize;
+
+    fn index(&self, (from, to): (usize, usize)) -> &usize {
+        &self.steps[from * self.valves.len() + to]
+    }
+}
+impl Graph for Cave {
+    type Node = usize;
+
+    fn neighbors(&self, &valve: &usize) -> impl Iterator<Item = (usize, usize)> {
+        self.valves[valve].neighbors.iter().map(|&n| (n, 1))
     }
 }
 #[aoc_generator(day16)]
-fn parse_input(input: &str) -> Cave {
-    let (_, name_idx, mut idx_valve) = input.lines().fold(
-        (0usize, HashMap::new(), HashMap::new()),
-        |(mut idx, mut name_idx, mut idx_valve), line| {
-            let (a, b) = line.split_once(';').unwrap();
-            let valve = &a[6..8];
-            let flow_rate = a[a.find('=').unwrap() + 1..].parse::<usize>().unwrap();
+pub fn parse_input(input: &str) -> Result<Cave, ParseError> {
+    let src = Source::new(16, input);
+    let mut idx = 0usize;
+    let mut name_idx = HashMap::new();
+    let mut idx_valve = HashMap::new();
+    let mut useful = 0;
+    let mut index_of = |name: &str| {
+        *name_idx.entry(name.to_string()).or_insert_with(|| {
+            idx += 1;
+            idx
+        })
+    };
+    for line in input.lines() {
+        let (a, b) = src.split_once(line, ";")?;
+        let valve = a.get(6..8).ok_or_else(|| src.error(a, "valve name"))?;
+        let (_, rate) = src.split_once(a, "=")?;
+        let flow_rate = src.parse::<usize>(rate, "flow rate")?;
+        useful += usize::from(flow_rate > 0);
+        if useful > MAX_USEFUL {
+            return Err(src.error(rate, "at most 64 valves with a flow rate"));
+        }
 
-            let valve_idx = *name_idx.entry(valve.to_string()).or_insert_with(|| {
-                idx += 1;
-                idx
-            });
+        let valve_idx = index_of(valve);
 
-            let mut neighbors = Vec::new();
-            b.split_ascii_whitespace()
-                .skip(4)
-                .map(|x| x.trim().replace(',', ""))
-                .for_each(|n| {
-                    let z = *name_idx.entry(n).or_insert_with(|| {
-                        idx += 1;
-                        idx
-                    });
-                    neighbors.push(z);
-                });
-            idx_valve.insert(
-                valve_idx,
-                Valve {
-                    flow_rate,
-                    neighbors,
-                },
-            );
-            (idx, name_idx, idx_valve)
-        },
-    );
+        let tunnels: Vec<&str> = b.split_ascii_whitespace().skip(4).collect();
+        if tunnels.is_empty() {
+            return Err(src.error(b, "tunnels to valves"));
+        }
+        let neighbors = tunnels
+            .into_iter()
+            .map(|n| index_of(n.trim_end_matches(',')))
+            .collect();
+        idx_valve.insert(
+            valve_idx,
+            Valve {
+                flow_rate,
+                neighbors,
+            },
+        );
+    }
     let mut res = Cave::new();
-    res.start_idx = *name_idx.get("AA").unwrap();
+    res.start_idx = *name_idx.get("AA").ok_or_else(|| src.missing("valve AA"))?;
     res.valve_map = name_idx;
 
     res.valves.push(Valve {
         flow_rate: usize::MAX,
         neighbors: vec![],
     });
-    let n = idx_valve.len();
-    for idx in 1..=n {
-        res.valves.push(idx_valve.remove(&idx).unwrap())
+    for idx in 1..=res.valve_map.len() {
+        // every valve that gets mentioned needs a line of its own
+        let valve = idx_valve.remove(&idx).ok_or_else(|| {
+            let name = res.valve_map.iter().find(|(_, &i)| i == idx).unwrap().0;
+            src.missing(&format!("a line for valve {name}"))
+        })?;
+        res.valves.push(valve)
     }
-    res
+    Ok(res)
 }
+type Memo = FxHashMap<(usize, usize, u64), usize>;
+// most pressure still to be released from `current` with `time` left,
+// when the valves in `opened` are already taken care of
 fn max_release(
     dist: &DistanceMatrix,
     cave: &Cave,
     current: usize,
     time: usize,
-    targets: &mut HashSet<usize>,
-) -> (usize, HashSet<usize>) {
-    targets.remove(&current);
-
+    opened: u64,
+    memo: &mut Memo,
+) -> usize {
+    if let Some(&best) = memo.get(&(current, time, opened)) {
+        return best;
+    }
     let mut max = 0;
-    let mut best_path = HashSet::new();
-
-    for t in targets.iter() {
-        // bound at 0
-        let remaining = time.saturating_sub(dist[current][*t]).saturating_sub(1);
+    for next in (0..dist.useful()).filter(|&v| opened & 1 << v == 0) {
+        let remaining = time.saturating_sub(dist[(current, next)]).saturating_sub(1);
         if remaining > 0 {
-            let mut flow = cave.valves[*t].flow_rate * remaining;
-            let (newflow, p) = max_release(dist, cave, *t, remaining, &mut targets.clone());
-            flow += newflow;
-
-            if flow > max {
-                max = flow;
-                best_path = p.clone();
-                best_path.insert(current);
+            let flow = dist.flow_rate(cave, next) * remaining;
+            let rest = max_release(dist, cave, next, remaining, opened | 1 << next, memo);
+            max = max.max(flow + rest);
+        }
+    }
+    memo.insert((current, time, opened), max);
+    max
+}
+// the order to open valves in to get max_release
+fn best_order(
+    dist: &DistanceMatrix,
+    cave: &Cave,
+    time: usize,
+    mut opened: u64,
+    memo: &mut Memo,
+) -> Vec<usize> {
+    let (mut current, mut time) = (dist.start(), time);
+    let mut order = vec![];
+    loop {
+        let best = max_release(dist, cave, current, time, opened, memo);
+        if best == 0 {
+            return order;
+        }
+        // whichever next valve the best release came from
+        let (next, remaining) = (0..dist.useful())
+            .filter(|&v| opened & 1 << v == 0)
+            .map(|v| (v, time.saturating_sub(dist[(current, v)]).saturating_sub(1)))
+            .find(|&(v, remaining)| {
+                remaining > 0
+                    && dist.flow_rate(cave, v) * remaining
+                        + max_release(dist, cave, v, remaining, opened | 1 << v, memo)
+                        == best
+            })
+            .unwrap();
+        order.push(next);
+        (current, time, opened) = (next, remaining, opened | 1 << next);
+    }
+}
+// the most pressure one agent can release for each set of valves it could
+// open, indexed by the set's bitmask
+struct ReleaseBySet<'a> {
+    dist: &'a DistanceMatrix,
+    cave: &'a Cave,
+    best: Vec<usize>,
+}
+impl<'a> ReleaseBySet<'a> {
+    fn new(dist: &'a DistanceMatrix, cave: &'a Cave, time: usize) -> Self {
+        let mut sets = ReleaseBySet {
+            dist,
+            cave,
+            best: vec![0; 1 << dist.useful()],
+        };
+        sets.visit(dist.start(), time, 0, 0);
+        sets
+    }
+    // every order of opening valves we have time for
+    fn visit(&mut self, current: usize, time: usize, opened: usize, released: usize) {
+        self.best[opened] = self.best[opened].max(released);
+        for valve in (0..self.dist.useful()).filter(|&v| opened & 1 << v == 0) {
+            let remaining = time
+                .saturating_sub(self.dist[(current, valve)])
+                .saturating_sub(1);
+            if remaining > 0 {
+                let flow = self.dist.flow_rate(self.cave, valve) * remaining;
+                self.visit(valve, remaining, opened | 1 << valve, released + flow);
             }
         }
     }
-    (max, best_path)
+    // share the valves out between `agents` so that together they release
+    // the most, each agent only opens valves from its own share
+    fn split(&self, agents: usize) -> Vec<usize> {
+        let all = self.best.len() - 1;
+        // most[k][set] is the best k agents can do between them with set
+        let mut most = vec![vec![0; self.best.len()]];
+        for k in 1..=agents {
+            let fewer = &most[k - 1];
+            let row = (0..=all)
+                .map(|set| {
+                    subsets(set)
+                        .map(|share| self.best[share] + fewer[set ^ share])
+                        .max()
+                        .unwrap()
+                })
+                .collect();
+            most.push(row);
+        }
+        // then walk back through which share each agent took
+        let mut set = all;
+        let mut shares = vec![];
+        for k in (1..=agents).rev() {
+            let share = subsets(set)
+                .find(|&share| self.best[share] + most[k - 1][set ^ share] == most[k][set])
+                .unwrap();
+            shares.push(share);
+            set ^= share;
+        }
+        shares
+    }
+}
+// every subset of set, from set itself down to the empty set
+fn subsets(set: usize) -> impl Iterator<Item = usize> {
+    iter::successors(Some(set), move |&sub| (sub != 0).then(|| (sub - 1) & set))
+}
+// the best `agents` can do working together for `minutes`, you first and
+// then however many elephants, for variants beyond part 1 and 2's
+pub fn solve(cave: &Cave, agents: usize, minutes: usize) -> Result<Schedule, TooManyValves> {
+    let dist = cave.calc_distances();
+    // nobody to open anything
+    if agents == 0 {
+        return Ok(Schedule::new(cave, &dist, minutes, &[]));
+    }
+    if agents != 1 && dist.useful() > MAX_SHARED {
+        let useful = dist.useful();
+        return Err(TooManyValves { useful, agents });
+    }
+    let mut memo = Memo::default();
+    let plans: Vec<(Actor, Vec<usize>)> = if agents == 1 {
+        vec![(Actor::You, best_order(&dist, cave, minutes, 0, &mut memo))]
+    } else {
+        let all = (1 << dist.useful()) - 1;
+        let shares = ReleaseBySet::new(&dist, cave, minutes).split(agents);
+        (shares.into_iter().enumerate())
+            .map(|(i, share)| {
+                let actor = if i == 0 {
+                    Actor::You
+                } else {
+                    Actor::Elephant(i)
+                };
+                // everything outside an agent's share counts as opened already
+                let order = best_order(&dist, cave, minutes, all ^ share as u64, &mut memo);
+                (actor, order)
+            })
+            .collect()
+    };
+    Ok(Schedule::new(cave, &dist, minutes, &plans))
 }
 #[aoc(day16, part1)]
-fn part1(input: &Cave) -> usize {
-    let distances = input.calc_distances();
-
-    let mut targets = HashSet::from_iter(
-        input
-            .valves
-            .iter()
-            .enumerate()
-            .filter(|&(i, r)| r.flow_rate > 0 || i == input.start_idx)
-            .map(|(i, _)| i),
-    );
-
-    max_release(&distances, input, input.start_idx, 30, &mut targets).0
+fn part1(input: &Cave) -> Result<usize, TooManyValves> {
+    Ok(solve(input, 1, 30)?.total)
+}
+#[aoc(day16, part1, schedule)]
+fn part1_schedule(input: &Cave) -> Result<String, TooManyValves> {
+    let plan = solve(input, 1, 30)?;
+    Ok(format!("{} pressure released\n\n{plan}", plan.total))
 }
+// you and the elephant open different valves, so try every way of
+// splitting them up
 #[aoc(day16, part2)]
-fn part2(input: &Cave) -> usize {
-    let distances = input.calc_distances();
+fn part2(input: &Cave) -> Result<usize, TooManyValves> {
+    Ok(solve(input, 2, 26)?.total)
+}
+#[aoc(day16, part2, schedule)]
+fn part2_schedule(input: &Cave) -> Result<String, TooManyValves> {
+    let plan = solve(input, 2, 26)?;
+    Ok(format!("{} pressure released\n\n{plan}", plan.total))
+}
 
-    let mut targets = HashSet::from_iter(
-        input
-            .valves
-            .iter()
-            .enumerate()
-            .filter(|&(i, r)| r.flow_rate > 0 || i == input.start_idx)
-            .map(|(i, _)| i),
-    );
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Actor {
+    You,
+    // numbered from 1
+    Elephant(usize),
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    Move(String),
+    // the valve and its flow rate
+    Open(String, usize),
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Step {
+    minute: usize,
+    actor: Actor,
+    action: Action,
+    // pressure released so far, up to the end of this minute
+    released: usize,
+}
+// what everyone does minute by minute, in order
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    agents: usize,
+    minutes: usize,
+    steps: Vec<Step>,
+    pub total: usize,
+}
+impl Schedule {
+    // `plans` are the valves each actor opens, in order, numbered as in `dist`
+    fn new(
+        cave: &Cave,
+        dist: &DistanceMatrix,
+        minutes: usize,
+        plans: &[(Actor, Vec<usize>)],
+    ) -> Self {
+        let mut steps = vec![];
+        for (actor, order) in plans {
+            let (mut current, mut minute) = (cave.start_idx, 0);
+            let step = |minute, action| Step {
+                minute,
+                actor: *actor,
+                action,
+                released: 0,
+            };
+            for &valve in order {
+                let valve = dist.valves[valve];
+                let route = search::bfs(cave, [current], |&v| v == valve).unwrap();
+                for &room in &route.nodes[1..] {
+                    minute += 1;
+                    steps.push(step(minute, Action::Move(cave.name(room).to_string())));
+                }
+                minute += 1;
+                let flow_rate = cave.valves[valve].flow_rate;
+                steps.push(step(
+                    minute,
+                    Action::Open(cave.name(valve).to_string(), flow_rate),
+                ));
+                current = valve;
+            }
+        }
+        steps.sort_by_key(|s| (s.minute, s.actor));
 
-    let (human_release, human_path) =
-        max_release(&distances, input, input.start_idx, 26, &mut targets);
+        // a valve opened during a minute only starts releasing the minute after
+        let (mut released, mut rate) = (0, 0);
+        let mut rest = &mut steps[..];
+        for minute in 1..=minutes {
+            released += rate;
+            let now = rest.iter().take_while(|s| s.minute == minute).count();
+            let (this_minute, later) = rest.split_at_mut(now);
+            for step in this_minute {
+                step.released = released;
+                if let Action::Open(_, flow_rate) = step.action {
+                    rate += flow_rate;
+                }
+            }
+            rest = later;
+        }
+        Schedule {
+            agents: plans.len(),
+            minutes,
+            steps,
+            total: released,
+        }
+    }
+}
+// the same narration as the puzzle's walkthrough
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut open: Vec<(&str, usize)> = vec![];
+        let mut steps = self.steps.iter().peekable();
+        for minute in 1..=self.minutes {
+            if minute > 1 {
+                writeln!(f)?;
+            }
+            writeln!(f, "== Minute {minute} ==")?;
+            open.sort();
+            let releasing: usize = open.iter().map(|&(_, flow)| flow).sum();
+            let names: Vec<&str> = open.iter().map(|&(name, _)| name).collect();
+            match names.as_slice() {
+                [] => writeln!(f, "No valves are open.")?,
+                [one] => writeln!(f, "Valve {one} is open, releasing {releasing} pressure.")?,
+                [a, b] => writeln!(
+                    f,
+                    "Valves {a} and {b} are open, releasing {releasing} pressure."
+                )?,
+                [most @ .., last] => writeln!(
+                    f,
+                    "Valves {}, and {last} are open, releasing {releasing} pressure.",
+                    most.join(", ")
+                )?,
+            }
+            while let Some(step) = steps.next_if(|s| s.minute == minute) {
+                let (who, moves, opens) = match step.actor {
+                    Actor::You => ("You".to_string(), "move", "open"),
+                    Actor::Elephant(_) if self.agents == 2 => {
+                        ("The elephant".to_string(), "moves", "opens")
+                    }
+                    Actor::Elephant(n) => (format!("Elephant {n}"), "moves", "opens"),
+                };
+                match &step.action {
+                    Action::Move(name) => writeln!(f, "{who} {moves} to valve {name}.")?,
+                    Action::Open(name, flow_rate) => {
+                        writeln!(f, "{who} {opens} valve {name}.")?;
+                        open.push((name, *flow_rate));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
 
-    let mut cave_elephant = Cave::new();
-    cave_elephant.start_idx = input.start_idx;
-    cave_elephant.valve_map = input.valve_map.clone();
+// each agent's route gets its own colour
+const ROUTE_COLORS: [&str; 4] = ["red", "blue", "darkgreen", "orange"];
+// the tunnels as a graphviz graph, with each valve labelled by its flow rate
+// with `collapse` only the start and the valves with flow are drawn, and the
+// corridors between them become edges labelled with their length
+fn to_dot(cave: &Cave, plan: Option<&Schedule>, collapse: bool) -> String {
+    let keep = |idx: usize| !collapse || idx == cave.start_idx || cave.valves[idx].flow_rate > 0;
+    let pair = |a: usize, b: usize| {
+        let (a, b) = (cave.name(a), cave.name(b));
+        (a.min(b), a.max(b))
+    };
+    // both ends of each edge, in name order, with the corridor length
+    let mut edges: BTreeMap<(&str, &str), Option<usize>> = BTreeMap::new();
+    if collapse {
+        let dist = cave.calc_distances();
+        let len = dist.valves.len();
+        for (a, b) in (0..len).tuple_combinations() {
+            let (from, to) = (dist.valves[a], dist.valves[b]);
+            let steps = dist[(a, b)];
+            // leave it out if the way there goes past another valve we drew
+            let via = |k: usize| {
+                ![from, to].contains(&dist.valves[k])
+                    && dist[(a, k)].saturating_add(dist[(k, b)]) == steps
+            };
+            if from != to && steps != usize::MAX && !(0..len).any(via) {
+                edges.insert(pair(from, to), Some(steps));
+            }
+        }
+    } else {
+        for (a, valve) in cave.valves.iter().enumerate().skip(1) {
+            for &b in &valve.neighbors {
+                edges.insert(pair(a, b), None);
+            }
+        }
+    }
 
-    for (idx, valve) in input.valves.iter().enumerate() {
-        let v = Valve {
-            flow_rate: valve.flow_rate,
-            neighbors: valve
-                .neighbors
-                .iter()
-                .filter(|n| !human_path.contains(n))
-                .copied()
-                .collect(),
+    // which agent walked down each edge first
+    let mut route = BTreeMap::new();
+    let steps = plan.map_or(&[][..], |p| &p.steps);
+    for actor in steps.iter().map(|s| s.actor).unique() {
+        let color = match actor {
+            Actor::You => ROUTE_COLORS[0],
+            Actor::Elephant(n) => ROUTE_COLORS[n % ROUTE_COLORS.len()],
         };
+        let (mut at, mut walked) = (cave.start_idx, 0);
+        for step in steps.iter().filter(|s| s.actor == actor) {
+            if let Action::Move(name) = &step.action {
+                let room = cave.valve_map[name];
+                walked += 1;
+                if keep(room) {
+                    let edge = pair(at, room);
+                    edges.entry(edge).or_insert(collapse.then_some(walked));
+                    route.entry(edge).or_insert(color);
+                    (at, walked) = (room, 0);
+                }
+            }
+        }
+    }
 
-        if human_path.contains(&idx) && idx != input.start_idx {
-            cave_elephant.valves.push(Valve {
-                flow_rate: 0,
-                neighbors: vec![],
-            });
+    let mut dot = String::from("graph cave {\n");
+    let nodes = (1..cave.valves.len()).filter(|&i| keep(i));
+    for (name, idx) in nodes.map(|i| (cave.name(i), i)).sorted() {
+        let flow_rate = cave.valves[idx].flow_rate;
+        let shape = if idx == cave.start_idx {
+            ", shape=doublecircle"
         } else {
-            cave_elephant.valves.push(v);
+            ""
+        };
+        dot += &format!("    {name} [label=\"{name}\\nflow {flow_rate}\"{shape}];\n");
+    }
+    for (edge @ (a, b), steps) in edges {
+        let mut attrs = vec![];
+        if let Some(steps) = steps {
+            attrs.push(format!("label={steps}"));
+        }
+        if let Some(color) = route.get(&edge) {
+            attrs.push(format!("color={color}, penwidth=3"));
         }
+        dot += &match attrs.is_empty() {
+            true => format!("    {a} -- {b};\n"),
+            false => format!("    {a} -- {b} [{}];\n", attrs.join(", ")),
+        };
     }
+    dot += "}\n";
+    dot
+}
+// the valves worth opening and the corridors between them, with the route
+// you and the elephant take
+#[aoc(day16, part2, dot)]
+fn part2_dot(input: &Cave) -> Result<String, TooManyValves> {
+    let plan = solve(input, 2, 26)?;
+    Ok(format!("\n{}", to_dot(input, Some(&plan), true)))
+}
 
-    let mut elephant_targets = HashSet::from_iter(
-        cave_elephant
-            .valves
-            .iter()
-            .enumerate()
-            .filter(|&(i, r)| r.flow_rate > 0 || i == cave_elephant.start_idx)
-            .map(|(i, _)| i),
-    );
-
-    let (elephant_release, _) = max_release(
-        &distances,
-        &cave_elephant,
-        cave_elephant.start_idx,
-        26,
-        &mut elephant_targets,
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
 
-    human_release + elephant_release
+    #[test]
+    fn example_answers() {
+        let input = parse_input(&example(16)).unwrap();
+        assert_eq!(part1(&input).unwrap(), 1651);
+        assert_eq!(part2(&input).unwrap(), 1707);
+    }
+    // the walkthrough on the puzzle page, give or take ties
+    #[test]
+    fn example_schedules() {
+        let input = parse_input(&example(16)).unwrap();
+        let plan = solve(&input, 1, 30).unwrap();
+        assert_eq!(plan.total, 1651);
+        assert_eq!(plan.steps.last().unwrap().released, 1651 - 81 * 6);
+        let narration = plan.to_string();
+        assert!(narration.starts_with(
+            "== Minute 1 ==\nNo valves are open.\nYou move to valve DD.\n\n\
+             == Minute 2 ==\nNo valves are open.\nYou open valve DD.\n\n\
+             == Minute 3 ==\nValve DD is open, releasing 20 pressure.\nYou move to valve CC.\n"
+        ));
+        assert!(narration
+            .contains("== Minute 6 ==\nValves BB and DD are open, releasing 33 pressure.\n"));
+        assert!(narration.ends_with(
+            "== Minute 30 ==\nValves BB, CC, DD, EE, HH, and JJ are open, releasing 81 pressure.\n"
+        ));
+        let plan = solve(&input, 2, 26).unwrap();
+        assert_eq!(plan.total, 1707);
+        assert!(plan.to_string().contains("The elephant opens valve"));
+    }
+    #[test]
+    fn dot_export() {
+        let input = parse_input(&example(16)).unwrap();
+        let plan = solve(&input, 1, 30).unwrap();
+        let dot = to_dot(&input, Some(&plan), false);
+        assert!(
+            dot.starts_with("graph cave {\n    AA [label=\"AA\\nflow 0\", shape=doublecircle];\n")
+        );
+        // the walkthrough's route goes down every tunnel
+        assert!(dot.contains("    AA -- DD [color=red, penwidth=3];\n"));
+        assert_eq!(dot.matches("penwidth").count(), 10);
+        let dot = to_dot(&input, None, false);
+        assert!(dot.contains("    AA -- BB;\n"));
+        assert_eq!(dot.matches(" -- ").count(), 10);
+        let dot = to_dot(&input, None, true);
+        assert_eq!(dot.matches("flow ").count(), 7);
+        assert!(dot.contains("    AA -- JJ [label=2];\n"));
+        assert!(dot.contains("    EE -- HH [label=3];\n"));
+        assert!(!dot.contains("AA -- CC"));
+    }
+    #[test]
+    fn any_number_of_agents() {
+        let input = parse_input(&example(16)).unwrap();
+        assert_eq!(solve(&input, 0, 30).unwrap().total, 0);
+        // with enough time one agent gets round everything
+        assert_eq!(solve(&input, 1, 40).unwrap().total, 1651 + 81 * 10);
+        let plan = solve(&input, 3, 26).unwrap();
+        assert!(plan.total >= 1707);
+        assert!(plan.to_string().contains("Elephant 2 "));
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse_input(&real_input(16)).unwrap();
+        assert_eq!(part1(&input).unwrap().to_string(), answer(16, 1));
+        assert_eq!(part2(&input).unwrap().to_string(), answer(16, 2));
+    }
+    // a corridor of rooms leading off from AA, the flow rate of each given
+    // by `rate` from its index
+    fn corridor(names: &[String], rate: impl Fn(usize) -> usize) -> Cave {
+        let mut input = format!(
+            "Valve AA has flow rate=0; tunnels lead to valves {}\n",
+            names[0]
+        );
+        for (i, name) in names.iter().enumerate() {
+            let prev = if i == 0 { "AA" } else { &names[i - 1] };
+            let next = names.get(i + 1).map_or(String::new(), |n| format!(", {n}"));
+            let rate = rate(i);
+            input += &format!(
+                "Valve {name} has flow rate={rate}; tunnels lead to valves {prev}{next}\n"
+            );
+        }
+        parse_input(input.trim_end()).unwrap()
+    }
+    // a corridor of 80 rooms, with one valve worth opening 10 steps in
+    #[test]
+    fn more_than_sixty_rooms() {
+        let names: Vec<String> = (0..80)
+            .map(|i| format!("{}{}", (b'B' + i / 26) as char, (b'A' + i % 26) as char))
+            .collect();
+        let cave = corridor(&names, |i| if i == 9 { 10 } else { 0 });
+        assert_eq!(cave.valves.len(), 82);
+        assert_eq!(part1(&cave).unwrap(), 10 * (30 - 11));
+    }
+    // too many valves to share out between you and the elephant
+    #[test]
+    fn too_many_to_share() {
+        let names: Vec<String> = (0..MAX_SHARED + 1)
+            .map(|i| format!("B{}", (b'A' + i as u8) as char))
+            .collect();
+        let cave = corridor(&names, |_| 1);
+        assert!(part1(&cave).is_ok());
+        let error = part2(&cave).unwrap_err();
+        assert_eq!(
+            error,
+            TooManyValves {
+                useful: MAX_SHARED + 1,
+                agents: 2
+            }
+        );
+        assert!(error.to_string().contains("at most 18"));
+        // and no agents at all doesn't try
+        assert_eq!(solve(&cave, 0, 30).unwrap().total, 0);
+    }
 }
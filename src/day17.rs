@@ -1,4 +1,4 @@
-use itertools::Itertools;
+use crate::error::{ParseError, Source};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
@@ -7,81 +7,120 @@ enum Wind {
     Left,
     Right,
 }
-#[derive(Copy, Clone)]
-struct Shape(u32);
-const LEFT_EDGE: u32 = 0x40404040;
-const RIGHT_EDGE: u32 = 0x01010101;
-impl Shape {
-    const fn all_shapes() -> [Self; 5] {
-        [
-            Self(0x0000001E),
-            Self(0x00081C08),
-            Self(0x0004041C),
-            Self(0x10101010),
-            Self(0x00001818),
-        ]
-    }
-    const fn intersects(&self, mask: u32) -> bool {
-        self.0 & mask != 0
+// tower rows are bitmasks with the leftmost column in the highest bit used
+const MAX_WIDTH: usize = 64;
+// the puzzle's rocks, in the order they fall
+const ROCKS: &str = "\
+####
+
+.#.
+###
+.#.
+
+..#
+..#
+###
+
+#
+#
+#
+#
+
+##
+##";
+#[derive(Debug, Clone, PartialEq)]
+struct Rock {
+    // rows from the bottom up, two in from the left wall where it appears
+    rows: Vec<u64>,
+    // how far it can be blown right from there before it hits the wall
+    room: i32,
+}
+impl Rock {
+    // the rows moved `x` columns right of where the rock appears, or left
+    // if `x` is negative
+    fn rows_at(&self, x: i32) -> impl Iterator<Item = u64> + '_ {
+        (self.rows.iter()).map(move |&row| if x >= 0 { row >> x } else { row << -x })
     }
-    fn blow(&mut self, wind: Wind, mask: u32) {
-        let new_pos = match wind {
-            Wind::Left => {
-                // make sure we arent at the left edge yet
-                if self.0 & LEFT_EDGE == 0 {
-                    self.0 << 1
-                } else {
-                    return;
+}
+#[derive(Debug, Clone)]
+struct Chamber {
+    width: usize,
+    rocks: Vec<Rock>,
+}
+impl Chamber {
+    // rocks drawn with # and ., blank lines in between, like the puzzle
+    fn new(rocks: &str, width: usize) -> Result<Self, ParseError> {
+        let src = Source::new(17, rocks);
+        if !(3..=MAX_WIDTH).contains(&width) {
+            let expected = format!("rocks for a chamber 3 to {MAX_WIDTH} wide, not {width}");
+            return Err(src.missing(&expected));
+        }
+        let rocks = (rocks.split("\n\n"))
+            .map(|art| {
+                let mut rows = vec![];
+                let mut rock_width = 0;
+                for line in art.lines() {
+                    if line.chars().count() + 2 > width {
+                        return Err(src.error(line, "a rock that fits two in from the left wall"));
+                    }
+                    let mut row = 0;
+                    for (i, c) in line.char_indices() {
+                        row <<= 1;
+                        match c {
+                            '#' => row |= 1,
+                            '.' => {}
+                            _ => return Err(src.char_error(line, i, "# or .")),
+                        }
+                    }
+                    rows.push(row << (width - 2 - line.len()));
+                    rock_width = rock_width.max(line.len());
                 }
-            }
-            Wind::Right => {
-                // make sure we aren't at the right edge yet
-                if self.0 & RIGHT_EDGE == 0 {
-                    self.0 >> 1
-                } else {
-                    return;
+                if rows.iter().all(|&row| row == 0) {
+                    return Err(src.error(art, "a rock with at least one #"));
                 }
-            }
-        };
-        // make sure we won't collide into any rocks
-        if new_pos & mask == 0 {
-            self.0 = new_pos;
-        }
+                rows.reverse();
+                let room = (width - 2 - rock_width) as i32;
+                Ok(Rock { rows, room })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Chamber { width, rocks })
     }
-    fn into_bytes(self) -> impl Iterator<Item = u8> {
-        self.0.to_le_bytes().into_iter().take_while(|b| *b != 0)
+    fn standard() -> Self {
+        Self::new(ROCKS, 7).unwrap()
     }
 }
-// bitmask representing row of tower
-fn tower_mask(tower: &[u8], height: usize) -> u32 {
-    if height >= tower.len() {
-        0
-    } else {
-        tower[height..]
-            .iter()
-            .take(4)
-            .rev()
-            .fold(0u32, |acc, b| (acc << 8) | *b as u32)
-    }
+// does the rock hit anything in the tower with its bottom row at `height`,
+// `x` columns right of where it appeared
+fn collides(tower: &[u64], height: usize, rock: &Rock, x: i32) -> bool {
+    height < tower.len()
+        && (rock.rows_at(x).enumerate())
+            .any(|(i, row)| tower.get(height + i).is_some_and(|t| t & row != 0))
 }
-fn drop_rock(tower: &mut Vec<u8>, wind: &[Wind], mut wind_idx: usize, mut shape: Shape) -> usize {
+fn drop_rock(tower: &mut Vec<u64>, wind: &[Wind], mut wind_idx: usize, rock: &Rock) -> usize {
     let mut height = tower.len() + 3;
+    let mut x = 0;
 
     loop {
         let wind_dir = wind[wind_idx];
         // wrap wind_idx back to 0
         wind_idx = (wind_idx + 1) % wind.len();
 
-        shape.blow(wind_dir, tower_mask(tower, height));
-        // the shape comes to rest
-        if height == 0 || shape.intersects(tower_mask(tower, height - 1)) {
-            // work from bottom to top
-            for byte in shape.into_bytes() {
-                // or into the tower
+        // make sure we aren't at the edge yet and won't collide into any rocks
+        let blown = match wind_dir {
+            Wind::Left => x - 1,
+            Wind::Right => x + 1,
+        };
+        if (-2..=rock.room).contains(&blown) && !collides(tower, height, rock, blown) {
+            x = blown;
+        }
+        // the rock comes to rest
+        if height == 0 || collides(tower, height - 1, rock, x) {
+            // work from bottom to top, or into the tower
+            for row in rock.rows_at(x) {
                 if height < tower.len() {
-                    tower[height] |= byte;
+                    tower[height] |= row;
                 } else {
-                    tower.push(byte);
+                    tower.push(row);
                 }
                 height += 1;
             }
@@ -92,51 +131,80 @@ fn drop_rock(tower: &mut Vec<u8>, wind: &[Wind], mut wind_idx: usize, mut shape:
     }
 }
 #[aoc_generator(day17)]
-fn parse(input: &str) -> Vec<Wind> {
-    input
-        .chars()
-        .map(|c| match c {
-            '<' => Wind::Left,
-            '>' => Wind::Right,
-            _ => unreachable!(),
+fn parse(input: &str) -> Result<Vec<Wind>, ParseError> {
+    let src = Source::new(17, input);
+    let jets = input.trim_end();
+    jets.char_indices()
+        .map(|(i, c)| match c {
+            '<' => Ok(Wind::Left),
+            '>' => Ok(Wind::Right),
+            _ => Err(src.char_error(jets, i, "< or >")),
         })
         .collect()
 }
-#[aoc(day17, part1)]
-fn part1(input: &[Wind]) -> usize {
-    let num_rocks = 2022;
+// height of the tower after `num_rocks` rocks, simulating every one
+fn simulate(wind: &[Wind], chamber: &Chamber, num_rocks: usize) -> usize {
     let mut tower = Vec::with_capacity(num_rocks * 4);
 
     let mut wind_idx = 0;
-    for shape in Shape::all_shapes().into_iter().cycle().take(num_rocks) {
-        wind_idx = drop_rock(&mut tower, input, wind_idx, shape);
+    for rock in chamber.rocks.iter().cycle().take(num_rocks) {
+        wind_idx = drop_rock(&mut tower, wind, wind_idx, rock);
     }
     tower.len()
 }
-#[aoc(day17, part2)]
-fn part2(input: &[Wind]) -> usize {
-    let num_rocks = 1000000000000;
-    let mut seen: HashMap<(u64, usize, usize), (usize, usize)> = HashMap::with_capacity(1024);
+// deepest surface worth comparing, past this we just keep dropping rocks
+const MAX_SURFACE: usize = 128;
+// the empty space a rock could still get into, as masks from the top row of
+// the tower down. rocks only ever move down, left or right, so it's the
+// space reachable from above that way; anything below it can never matter.
+// None if it goes deeper than MAX_SURFACE rows
+fn surface(tower: &[u64], width: usize) -> Option<Vec<u64>> {
+    let full = u64::MAX >> (MAX_WIDTH - width);
+    let mut reach = full;
+    let mut profile = vec![];
+    for &row in tower.iter().rev() {
+        let empty = !row & full;
+        reach &= empty;
+        // spread sideways through the gaps in this row
+        loop {
+            let spread = (reach | reach << 1 | reach >> 1) & empty;
+            if spread == reach {
+                break;
+            }
+            reach = spread;
+        }
+        if reach == 0 {
+            break;
+        }
+        if profile.len() == MAX_SURFACE {
+            return None;
+        }
+        profile.push(reach);
+    }
+    Some(profile)
+}
+// height of the tower after `num_rocks` rocks, skipping ahead once the
+// top of the tower starts repeating. that needs the surface to stay shallow,
+// a column nothing ever lands in means simulating every rock
+fn tower_height(wind: &[Wind], chamber: &Chamber, num_rocks: usize) -> usize {
+    let mut seen: HashMap<(Vec<u64>, usize, usize), (usize, usize)> = HashMap::with_capacity(1024);
     let mut tower = Vec::with_capacity(1024);
 
     let mut cycled_height = 0;
     let mut wind_idx = 0;
-    let shapes = Shape::all_shapes().into_iter().collect_vec();
+    let rocks = &chamber.rocks;
     let mut rock_count = 0;
     while rock_count < num_rocks {
-        let shape_idx = rock_count % shapes.len();
-        let shape = shapes[shape_idx];
+        let rock_idx = rock_count % rocks.len();
 
-        wind_idx = drop_rock(&mut tower, input, wind_idx, shape);
+        wind_idx = drop_rock(&mut tower, wind, wind_idx, &rocks[rock_idx]);
         rock_count += 1;
-        // no need to memoize if we are tiny tower
-        if tower.len() < 8 {
+        // the same surface with the same rock and jet next plays out the
+        // same way, just higher up
+        let Some(surface) = surface(&tower, chamber.width) else {
             continue;
-        }
-        // skyline is last 8 rows, nicely forms a 64 bit int
-        let skyline = u64::from_ne_bytes(tower[tower.len() - 8..].try_into().unwrap());
-
-        let state = (skyline, shape_idx, wind_idx);
+        };
+        let state = (surface, rock_idx, wind_idx);
 
         match seen.entry(state) {
             Entry::Occupied(e) => {
@@ -154,3 +222,106 @@ fn part2(input: &[Wind]) -> usize {
     }
     tower.len() + cycled_height
 }
+// the height of the tower for any set of rocks (drawn like `ROCKS`) falling
+// in a chamber up to 64 wide
+pub fn tower_with(
+    jets: &str,
+    rocks: &str,
+    width: usize,
+    num_rocks: usize,
+) -> Result<usize, ParseError> {
+    let chamber = Chamber::new(rocks, width)?;
+    Ok(tower_height(&parse(jets)?, &chamber, num_rocks))
+}
+#[aoc(day17, part1)]
+fn part1(input: &[Wind]) -> usize {
+    simulate(input, &Chamber::standard(), 2022)
+}
+#[aoc(day17, part2)]
+fn part2(input: &[Wind]) -> usize {
+    tower_height(input, &Chamber::standard(), 1000000000000)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(17)).unwrap();
+        assert_eq!(part1(&input), 3068);
+        assert_eq!(part2(&input), 1514285714288);
+    }
+    #[test]
+    fn custom_rocks() {
+        let wind = parse(&example(17)).unwrap();
+        let standard = Chamber::standard();
+        assert_eq!(standard.rocks[0].rows, [0b0011110]);
+        assert_eq!(standard.rocks[2].rows, [0b0011100, 0b0000100, 0b0000100]);
+        assert_eq!(standard.rocks[4].room, 3);
+
+        // skipping ahead over cycles gets the same height as dropping them all
+        let pieces = format!("{ROCKS}\n\n#..\n#..\n###\n\n.#\n##\n#.\n#.");
+        for width in [9, 12, 40, 64] {
+            let chamber = Chamber::new(&pieces, width).unwrap();
+            let n = 5000;
+            assert_eq!(
+                tower_height(&wind, &chamber, n),
+                simulate(&wind, &chamber, n)
+            );
+        }
+        let jets = example(17);
+        assert_eq!(tower_with(&jets, ROCKS, 7, 2022).unwrap(), 3068);
+
+        // a rock as wide as it can be just stacks up
+        let slab = "#".repeat(62);
+        let chamber = Chamber::new(&slab, 64).unwrap();
+        assert_eq!(tower_height(&wind, &chamber, 1000000000000), 1000000000000);
+
+        let error = Chamber::new(&slab, 63).unwrap_err();
+        assert_eq!(error.expected, "a rock that fits two in from the left wall");
+        assert!(tower_with(&jets, ROCKS, 65, 10).is_err());
+        let error = Chamber::new(ROCKS, 2).unwrap_err();
+        assert_eq!(error.expected, "rocks for a chamber 3 to 64 wide, not 2");
+        let error = Chamber::new("##\n\n#o#", 7).unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.found.as_str()),
+            (3, 2, "o")
+        );
+        let error = Chamber::new("##\n\n..", 7).unwrap_err();
+        assert_eq!(error.expected, "a rock with at least one #");
+    }
+    #[test]
+    fn cycles_in_wide_chambers() {
+        // jet patterns from a little xorshift generator
+        let mut seed = 0x2545f4914f6cdd1d_u64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let n = 20000;
+        for width in [7, 16, 32, 64] {
+            let chamber = Chamber::new(ROCKS, width).unwrap();
+            for _ in 0..10 {
+                let len = 10 + random() as usize % 200;
+                let wind: Vec<Wind> = (0..len)
+                    .map(|_| match random() % 2 {
+                        0 => Wind::Left,
+                        _ => Wind::Right,
+                    })
+                    .collect();
+                let expected = simulate(&wind, &chamber, n);
+                assert_eq!(tower_height(&wind, &chamber, n), expected, "{wind:?}");
+            }
+        }
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse(&real_input(17)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(17, 1));
+        assert_eq!(part2(&input).to_string(), answer(17, 2));
+    }
+}
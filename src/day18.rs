@@ -0,0 +1,91 @@
+use crate::error::{ParseError, Source};
+use fxhash::FxHashSet as HashSet;
+use itertools::Itertools;
+
+type Pos = (i32, i32, i32);
+const FACES: [Pos; 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+fn neighbors((x, y, z): Pos) -> impl Iterator<Item = Pos> {
+    FACES
+        .iter()
+        .map(move |&(dx, dy, dz)| (x + dx, y + dy, z + dz))
+}
+#[aoc_generator(day18)]
+fn parse(input: &str) -> Result<HashSet<Pos>, ParseError> {
+    let src = Source::new(18, input);
+    if input.trim().is_empty() {
+        return Err(src.missing("at least one x,y,z cube"));
+    }
+    input
+        .lines()
+        .map(|l| {
+            l.split(',')
+                .map(|n| src.parse(n.trim(), "coordinate"))
+                .collect::<Result<Vec<i32>, _>>()?
+                .into_iter()
+                .collect_tuple()
+                .ok_or_else(|| src.error(l, "x,y,z"))
+        })
+        .collect()
+}
+#[aoc(day18, part1)]
+fn part1(input: &HashSet<Pos>) -> usize {
+    input
+        .iter()
+        .flat_map(|&cube| neighbors(cube))
+        .filter(|n| !input.contains(n))
+        .count()
+}
+#[aoc(day18, part2)]
+fn part2(input: &HashSet<Pos>) -> usize {
+    // bounding box with a one cube gap so the steam can get all the way around
+    let (minx, maxx) = input.iter().map(|c| c.0).minmax().into_option().unwrap();
+    let (miny, maxy) = input.iter().map(|c| c.1).minmax().into_option().unwrap();
+    let (minz, maxz) = input.iter().map(|c| c.2).minmax().into_option().unwrap();
+    let in_bounds = |&(x, y, z): &Pos| {
+        (minx - 1..=maxx + 1).contains(&x)
+            && (miny - 1..=maxy + 1).contains(&y)
+            && (minz - 1..=maxz + 1).contains(&z)
+    };
+    // flood fill the outside, counting every face of lava we bump into
+    let start = (minx - 1, miny - 1, minz - 1);
+    let mut seen = HashSet::default();
+    seen.insert(start);
+    let mut stack = vec![start];
+    let mut faces = 0;
+    while let Some(pos) = stack.pop() {
+        for next in neighbors(pos).filter(in_bounds) {
+            if input.contains(&next) {
+                faces += 1;
+            } else if seen.insert(next) {
+                stack.push(next);
+            }
+        }
+    }
+    faces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::example;
+
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(18)).unwrap();
+        assert_eq!(part1(&input), 64);
+        assert_eq!(part2(&input), 58);
+    }
+    #[test]
+    fn empty_input() {
+        let error = parse("").unwrap_err();
+        assert_eq!(error.expected, "at least one x,y,z cube");
+    }
+}
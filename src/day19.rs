@@ -1,167 +1,513 @@
-use crate::day19::Material::*;
+use crate::error::{ParseError, Source};
+use fxhash::FxHashSet as HashSet;
 use itertools::Itertools;
-use lazy_static::lazy_static;
 use rayon::prelude::*;
-use regex::Regex;
 use std::cmp::max;
+use std::fmt;
+use std::iter;
+// index into a blueprint's materials
+type Material = usize;
 type RecipePart = (u32, Material);
-#[derive(Copy, Clone)]
-enum Material {
-    Ore = 0,
-    Clay = 1,
-    Obsidian = 2,
-    Geode = 3,
-}
 
-struct Blueprint {
+// we always start with an ore robot, and are always after geodes
+const ORE: Material = 0;
+const GEODE: &str = "geode";
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Blueprint {
     id: u32,
-    recipes: [Vec<RecipePart>; 4],
+    // every material mentioned, robot i collects materials[i]
+    materials: Vec<String>,
+    // None for the materials there's no robot for
+    recipes: Vec<Option<Vec<RecipePart>>>,
 }
 impl Blueprint {
-    fn from_line(line: &str) -> Self {
-        lazy_static! {
-            static ref re: Regex = Regex::new(r"(\d+)").unwrap();
+    // Blueprint <id>: Each <material> robot costs <n> <material> and <n> <material>. ...
+    // costs can be separated by "and" or commas, or both
+    fn from_line(src: &Source, line: &str) -> Result<Self, ParseError> {
+        let (head, rest) = src.split_once(line, ":")?;
+        let id = head
+            .strip_prefix("Blueprint ")
+            .ok_or_else(|| src.error(head, "\"Blueprint <id>\""))?;
+        let mut blueprint = Blueprint {
+            id: src.parse(id, "blueprint id")?,
+            materials: vec!["ore".to_string()],
+            recipes: vec![None],
+        };
+        for sentence in rest.split('.').map(str::trim).filter(|s| !s.is_empty()) {
+            let (robot, costs) = sentence
+                .strip_prefix("Each ")
+                .and_then(|s| s.split_once(" robot costs "))
+                .ok_or_else(|| src.error(sentence, "\"Each <material> robot costs ...\""))?;
+            let robot = blueprint.material(robot);
+            if blueprint.recipes[robot].is_some() {
+                return Err(src.error(sentence, "one recipe per robot"));
+            }
+            let mut recipe = vec![];
+            for cost in costs.split(',').flat_map(|c| c.split(" and ")) {
+                let cost = cost.trim();
+                if cost.is_empty() {
+                    continue;
+                }
+                let (amount, material) = src.split_once(cost, " ")?;
+                recipe.push((src.parse(amount, "amount")?, blueprint.material(material)));
+            }
+            if recipe.is_empty() {
+                return Err(src.error(costs, "robot costs"));
+            }
+            blueprint.recipes[robot] = Some(recipe);
         }
-        let nums: Vec<u32> = re
-            .captures_iter(line)
-            .map(|c| c[1].parse::<u32>().unwrap())
-            .collect_vec();
-        Self {
-            id: nums[0],
-            recipes: [
-                vec![(nums[1], Ore)],
-                vec![(nums[2], Ore)],
-                vec![(nums[3], Ore), (nums[4], Clay)],
-                vec![(nums[5], Ore), (nums[6], Obsidian)],
-            ],
+        blueprint.material(GEODE);
+        Ok(blueprint)
+    }
+    // the index of a material, adding it if it's new
+    fn material(&mut self, name: &str) -> Material {
+        match self.materials.iter().position(|m| m == name) {
+            Some(i) => i,
+            None => {
+                self.materials.push(name.to_string());
+                self.recipes.push(None);
+                self.materials.len() - 1
+            }
         }
     }
+    fn geode(&self) -> Material {
+        self.materials.iter().position(|m| m == GEODE).unwrap()
+    }
+    fn recipe(&self, robot: Material) -> &[RecipePart] {
+        self.recipes[robot].as_deref().unwrap_or(&[])
+    }
 }
-#[derive(Copy, Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 struct SearchState {
     time_remaining: u32,
-    robots: [u32; 4],
-    materials: [u32; 4],
-}
-fn optimistic_max(state: &SearchState, mat: Material) -> u32 {
-    let mat = mat as usize;
-    let i = state.time_remaining;
-    // assume we can build a new robot every turn
-    state.materials[mat] + state.robots[mat] * i + i * (i - 1) / 2
-}
-// Finds the maximum of any material needed in any recipe
-fn get_max_materials(blueprint: &Blueprint) -> [u32; 4] {
-    let mut maxs = [0, 0, 0, u32::MAX];
-
-    for recipe in &blueprint.recipes {
+    robots: Vec<u32>,
+    materials: Vec<u32>,
+}
+impl SearchState {
+    fn new(blueprint: &Blueprint, time_remaining: u32) -> Self {
+        let mut robots = vec![0; blueprint.materials.len()];
+        robots[ORE] = 1;
+        SearchState {
+            time_remaining,
+            materials: vec![0; robots.len()],
+            robots,
+        }
+    }
+    // what we end up with if we don't build anything else
+    fn idle(&self, material: Material) -> u32 {
+        self.materials[material] + self.robots[material] * self.time_remaining
+    }
+    // the state once `robot` is built, skipping the minutes spent saving up,
+    // None if we can't afford it in time for it to do anything
+    fn build_next(&self, robot: Material, blueprint: &Blueprint) -> Option<SearchState> {
+        let recipe = blueprint.recipes[robot].as_ref()?;
+        let mut wait = 0;
         for &(amount, material) in recipe {
-            let i = material as usize;
-            maxs[i] = max(maxs[i], amount);
+            let (have, income) = (self.materials[material], self.robots[material]);
+            if have < amount {
+                if income == 0 {
+                    return None;
+                }
+                wait = max(wait, (amount - have).div_ceil(income));
+            }
+        }
+        // it takes a minute to build, and has to have a minute left to collect
+        if wait + 1 >= self.time_remaining {
+            return None;
         }
+        let mut next = self.clone();
+        next.time_remaining -= wait + 1;
+        for (have, income) in next.materials.iter_mut().zip(&self.robots) {
+            *have += income * (wait + 1);
+        }
+        for &(amount, material) in recipe {
+            next.materials[material] -= amount;
+        }
+        next.robots[robot] += 1;
+        Some(next)
     }
-
-    return maxs;
-}
-impl SearchState {
-    fn can_build_robot(
-        &self,
-        robot_type: usize,
-        blueprint: &Blueprint,
-        max_materials: &[u32],
-    ) -> bool {
-        let recipe = &blueprint.recipes[robot_type];
-
-        let maxed_out = self.robots[robot_type] >= max_materials[robot_type];
-
-        !maxed_out
-            && recipe
-                .iter()
-                .all(|&(amt, mat)| self.materials[mat as usize] >= amt)
-    }
-    // Updates self to deduct materials and add one robot of the specified type
-    // We assume that the robot can be built
-    fn build_robot(&mut self, robot_type: usize, blueprint: &Blueprint) {
-        self.robots[robot_type] += 1;
-        for &(amount, material) in &blueprint.recipes[robot_type] {
-            self.materials[material as usize] -= amount;
+    // the most geodes we could possibly still get, if every kind of robot
+    // had its own stockpile of everything it needs, so one kind of robot
+    // never holds up another and we can build one of each every minute
+    // that's never worse than sharing, so the real answer can't beat it
+    fn upper_bound(&self, blueprint: &Blueprint) -> u32 {
+        let kinds = self.robots.len();
+        let geode = blueprint.geode();
+        let mut robots = self.robots.clone();
+        // stock[robot * kinds + material]
+        let mut stock: Vec<u32> = (0..kinds).flat_map(|_| self.materials.clone()).collect();
+        let mut geodes = self.materials[geode];
+        let mut built = vec![false; kinds];
+        for _ in 0..self.time_remaining {
+            for (robot, built) in built.iter_mut().enumerate() {
+                let stock = &mut stock[robot * kinds..(robot + 1) * kinds];
+                *built = blueprint.recipes[robot]
+                    .as_ref()
+                    .is_some_and(|r| r.iter().all(|&(amount, m)| stock[m] >= amount));
+                if *built {
+                    blueprint
+                        .recipe(robot)
+                        .iter()
+                        .for_each(|&(amount, m)| stock[m] -= amount);
+                }
+            }
+            for (i, have) in stock.iter_mut().enumerate() {
+                *have += robots[i % kinds];
+            }
+            geodes += robots[geode];
+            for (robots, &built) in robots.iter_mut().zip(&built) {
+                *robots += u32::from(built);
+            }
         }
+        geodes
     }
-    fn unbuild_robot(&mut self, robot_type: usize, blueprint: &Blueprint) {
-        self.robots[robot_type] -= 1;
-        for &(amount, material) in &blueprint.recipes[robot_type] {
-            self.materials[material as usize] += amount;
+}
+// the most of each material any robot needs, we can only spend that much
+// in a minute so there's no point collecting more
+fn get_max_materials(blueprint: &Blueprint) -> Vec<u32> {
+    let mut maxs = vec![0; blueprint.materials.len()];
+    for recipe in blueprint.recipes.iter().flatten() {
+        for &(amount, material) in recipe {
+            maxs[material] = max(maxs[material], amount);
         }
     }
+    maxs[blueprint.geode()] = u32::MAX;
+    maxs
 }
-fn score(blueprint: &Blueprint, time_remaining: u32) -> u32 {
-    let state = SearchState {
-        time_remaining,
-        robots: [1, 0, 0, 0],
-        materials: [0, 0, 0, 0],
-    };
+// the order to build robots in that gets the most geodes
+fn best_order(blueprint: &Blueprint, time_remaining: u32) -> Vec<Material> {
     let max_materials = get_max_materials(blueprint);
-    search(&state, blueprint, &max_materials, None, 0)
+    let mut best = (0, vec![]);
+    let state = SearchState::new(blueprint, time_remaining);
+    let mut seen = HashSet::default();
+    search(
+        &state,
+        blueprint,
+        &max_materials,
+        &mut seen,
+        &mut vec![],
+        &mut best,
+    );
+    best.1
 }
+fn score(blueprint: &Blueprint, time_remaining: u32) -> u32 {
+    plan(blueprint, time_remaining).geodes()
+}
+// branch on which robot to build next rather than on what to do each minute
+// `order` is how we got to `state`, `best` the most geodes so far and how.
+// different orders can end up in the same state, and everything that can
+// follow a state has already been searched the first time we got there
 fn search(
     state: &SearchState,
     blueprint: &Blueprint,
     max_mat: &[u32],
-    skipped: Option<&Vec<usize>>,
-    best: u32,
-) -> u32 {
-    if state.time_remaining == 1 {
-        return state.robots[3] + state.materials[3];
+    seen: &mut HashSet<SearchState>,
+    order: &mut Vec<Material>,
+    best: &mut (u32, Vec<Material>),
+) {
+    if !seen.insert(state.clone()) {
+        return;
     }
-
-    if optimistic_max(state, Geode) < best {
-        return 0;
+    let geode = blueprint.geode();
+    if state.idle(geode) > best.0 {
+        *best = (state.idle(geode), order.clone());
     }
-    if optimistic_max(state, Obsidian) < max_mat[2] {
-        return state.materials[3] + state.robots[3] * state.time_remaining;
+    if state.upper_bound(blueprint) <= best.0 {
+        return;
     }
-
-    let mut new_state = *state;
-    new_state.time_remaining -= 1;
-    (0..4).for_each(|i| new_state.materials[i] += new_state.robots[i]);
-
-    if state.can_build_robot(Geode as usize, blueprint, max_mat) {
-        new_state.build_robot(Geode as usize, blueprint);
-        return search(&new_state, blueprint, max_mat, None, best);
-    }
-    let robots_available = (0..3)
-        .filter(|i| state.can_build_robot(*i, blueprint, max_mat))
-        .collect_vec();
-    let mut best = best;
-
-    for &robot in &robots_available {
-        if skipped.map(|l| l.contains(&robot)).unwrap_or(false) {
+    // geode robots first, the sooner we find a good answer the more we prune
+    let robots = iter::once(geode).chain((0..max_mat.len()).rev().filter(|&r| r != geode));
+    for robot in robots {
+        // we can only spend so much of anything in a minute
+        if state.robots[robot] >= max_mat[robot] {
             continue;
         }
-
-        new_state.build_robot(robot, blueprint);
-        best = max(best, search(&new_state, blueprint, max_mat, None, best));
-        new_state.unbuild_robot(robot, blueprint);
+        if let Some(next) = state.build_next(robot, blueprint) {
+            order.push(robot);
+            search(&next, blueprint, max_mat, seen, order, best);
+            order.pop();
+        }
     }
-    let score = search(
-        &new_state,
-        blueprint,
-        max_mat,
-        Some(&robots_available),
-        best,
-    );
-    best = max(score, best);
+}
 
-    best
+// one minute of a plan
+struct Minute {
+    // the robot we started building, and what it cost
+    built: Option<(Material, Vec<RecipePart>)>,
+    // everything we have by the end of the minute
+    robots: Vec<u32>,
+    materials: Vec<u32>,
+}
+// minute by minute, the build order that gets the most geodes
+struct Plan {
+    materials: Vec<String>,
+    start: Vec<u32>,
+    minutes: Vec<Minute>,
+}
+impl Plan {
+    fn geodes(&self) -> u32 {
+        let geode = self.materials.iter().position(|m| m == GEODE).unwrap();
+        self.minutes.last().map_or(0, |m| m.materials[geode])
+    }
+}
+fn plan(blueprint: &Blueprint, time_remaining: u32) -> Plan {
+    let mut state = SearchState::new(blueprint, time_remaining);
+    let start = state.robots.clone();
+    let mut order = best_order(blueprint, time_remaining).into_iter().peekable();
+    let mut minutes = vec![];
+    for _ in 0..time_remaining {
+        // build the next robot as soon as we can afford it
+        let affordable = |&robot: &Material| {
+            let recipe = blueprint.recipe(robot);
+            recipe
+                .iter()
+                .all(|&(amount, m)| state.materials[m] >= amount)
+        };
+        let built = order.next_if(affordable);
+        if let Some(robot) = built {
+            for &(amount, material) in blueprint.recipe(robot) {
+                state.materials[material] -= amount;
+            }
+        }
+        for (have, income) in state.materials.iter_mut().zip(&state.robots) {
+            *have += income;
+        }
+        if let Some(robot) = built {
+            state.robots[robot] += 1;
+        }
+        minutes.push(Minute {
+            built: built.map(|robot| (robot, blueprint.recipe(robot).to_vec())),
+            robots: state.robots.clone(),
+            materials: state.materials.clone(),
+        });
+    }
+    Plan {
+        materials: blueprint.materials.clone(),
+        start,
+        minutes,
+    }
+}
+fn robot_name(material: &str) -> String {
+    match material {
+        GEODE => "geode-cracking robot".to_string(),
+        _ => format!("{material}-collecting robot"),
+    }
+}
+// the same narration as the puzzle's walkthrough
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = &self.materials;
+        let mut robots = &self.start;
+        for (i, minute) in self.minutes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "== Minute {} ==", i + 1)?;
+            if let Some((robot, cost)) = &minute.built {
+                let cost = cost
+                    .iter()
+                    .map(|&(amount, m)| format!("{amount} {}", names[m]))
+                    .join(" and ");
+                let robot = robot_name(&names[*robot]);
+                let article = if robot.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                    "an"
+                } else {
+                    "a"
+                };
+                writeln!(f, "Spend {cost} to start building {article} {robot}.")?;
+            }
+            for (m, &count) in robots.iter().enumerate().filter(|&(_, &n)| n > 0) {
+                let have = minute.materials[m];
+                let (plural, verb) = if count == 1 { ("", "s") } else { ("s", "") };
+                let robot = robot_name(&names[m]);
+                if names[m] == GEODE {
+                    let geodes = |n: u32| if n == 1 { "geode" } else { "geodes" };
+                    writeln!(
+                        f,
+                        "{count} {robot}{plural} crack{verb} {count} {}; you now have {have} open {}.",
+                        geodes(count),
+                        geodes(have)
+                    )?;
+                } else {
+                    let name = &names[m];
+                    writeln!(
+                        f,
+                        "{count} {robot}{plural} collect{verb} {count} {name}; you now have {have} {name}."
+                    )?;
+                }
+            }
+            if let Some((robot, _)) = minute.built {
+                let count = minute.robots[robot];
+                let robot = robot_name(&names[robot]);
+                writeln!(f, "The new {robot} is ready; you now have {count} of them.")?;
+            }
+            robots = &minute.robots;
+        }
+        Ok(())
+    }
 }
 #[aoc_generator(day19)]
-fn parse(input: &str) -> Vec<Blueprint> {
-    input.lines().map(Blueprint::from_line).collect()
+pub fn parse(input: &str) -> Result<Vec<Blueprint>, ParseError> {
+    let src = Source::new(19, input);
+    input
+        .lines()
+        .map(|l| Blueprint::from_line(&src, l))
+        .collect()
+}
+// how the geodes from each blueprint add up to an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    // blueprint id times geodes, summed
+    QualitySum,
+    // geodes from each blueprint, multiplied
+    GeodeProduct,
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Day19Params {
+    pub minutes: u32,
+    // only the first this many blueprints, or all of them if there are fewer
+    pub blueprints: usize,
+    pub aggregate: Aggregate,
+}
+impl Day19Params {
+    pub const PART1: Self = Day19Params {
+        minutes: 24,
+        blueprints: usize::MAX,
+        aggregate: Aggregate::QualitySum,
+    };
+    pub const PART2: Self = Day19Params {
+        minutes: 32,
+        blueprints: 3,
+        aggregate: Aggregate::GeodeProduct,
+    };
+}
+pub fn evaluate(input: &[Blueprint], params: Day19Params) -> u32 {
+    let blueprints = &input[..params.blueprints.min(input.len())];
+    let geodes = blueprints
+        .par_iter()
+        .map(|bp| (bp.id, score(bp, params.minutes)));
+    match params.aggregate {
+        Aggregate::QualitySum => geodes.map(|(id, geodes)| id * geodes).sum(),
+        Aggregate::GeodeProduct => geodes.map(|(_, geodes)| geodes).product(),
+    }
+}
+// the answer for blueprints with their own minutes, count and aggregation,
+// rather than part 1 or part 2's
+pub fn evaluate_with(input: &str, params: Day19Params) -> Result<u32, ParseError> {
+    Ok(evaluate(&parse(input)?, params))
 }
 #[aoc(day19, part1)]
 fn part1(input: &[Blueprint]) -> u32 {
-    input.par_iter().map(|bp| bp.id * score(bp, 24)).sum()
+    evaluate(input, Day19Params::PART1)
+}
+// how the blueprint that opens the most geodes goes about it
+#[aoc(day19, part1, plan)]
+fn part1_plan(input: &[Blueprint]) -> String {
+    let minutes = Day19Params::PART1.minutes;
+    let plans = input.par_iter().map(|bp| (bp.id, plan(bp, minutes)));
+    match plans.max_by_key(|(id, plan)| (plan.geodes(), std::cmp::Reverse(*id))) {
+        Some((id, plan)) => format!("blueprint {id}\n\n{plan}"),
+        None => String::new(),
+    }
 }
 #[aoc(day19, part2)]
 fn part2(input: &[Blueprint]) -> u32 {
-    input[..3].par_iter().map(|bp| score(bp, 32)).product()
+    evaluate(input, Day19Params::PART2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    // the example only has two blueprints, so part 2 multiplies both
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(19)).unwrap();
+        assert_eq!(part1(&input), 33);
+        assert_eq!(part2(&input), 56 * 62);
+        let params = Day19Params {
+            blueprints: 1,
+            ..Day19Params::PART1
+        };
+        assert_eq!(evaluate(&input, params), 9);
+        let params = Day19Params {
+            minutes: 24,
+            blueprints: 2,
+            aggregate: Aggregate::GeodeProduct,
+        };
+        assert_eq!(evaluate_with(&example(19), params).unwrap(), 9 * 12);
+        assert!(evaluate_with("Blueprint 1", params).is_err());
+    }
+    // blueprint 1 goes just like the walkthrough on the puzzle page
+    #[test]
+    fn example_plan() {
+        let input = parse(&example(19)).unwrap();
+        let plan = plan(&input[0], 24);
+        let builds: Vec<(usize, usize)> = (plan.minutes.iter().enumerate())
+            .filter_map(|(i, m)| Some((i + 1, m.built.as_ref()?.0)))
+            .collect();
+        assert_eq!(
+            builds,
+            [
+                (3, 1),
+                (5, 1),
+                (7, 1),
+                (11, 2),
+                (12, 1),
+                (15, 2),
+                (18, 3),
+                (21, 3)
+            ]
+        );
+        let narration = plan.to_string();
+        assert!(narration.starts_with(
+            "== Minute 1 ==\n\
+             1 ore-collecting robot collects 1 ore; you now have 1 ore.\n\n\
+             == Minute 2 ==\n\
+             1 ore-collecting robot collects 1 ore; you now have 2 ore.\n\n\
+             == Minute 3 ==\n\
+             Spend 2 ore to start building a clay-collecting robot.\n\
+             1 ore-collecting robot collects 1 ore; you now have 1 ore.\n\
+             The new clay-collecting robot is ready; you now have 1 of them.\n"
+        ));
+        assert!(narration.ends_with(
+            "== Minute 24 ==\n\
+             1 ore-collecting robot collects 1 ore; you now have 6 ore.\n\
+             4 clay-collecting robots collect 4 clay; you now have 41 clay.\n\
+             2 obsidian-collecting robots collect 2 obsidian; you now have 8 obsidian.\n\
+             2 geode-cracking robots crack 2 geodes; you now have 9 open geodes.\n"
+        ));
+    }
+    // a made-up material, and costs listed with commas
+    #[test]
+    fn variant_blueprint() {
+        let line = "Blueprint 7: Each ore robot costs 2 ore. Each clay robot costs 2 ore. \
+                    Each diamond robot costs 3 ore, 2 clay, and 1 obsidian. \
+                    Each obsidian robot costs 2 ore and 4 clay. \
+                    Each geode robot costs 2 diamond and 2 obsidian.";
+        let blueprint = &parse(line).unwrap()[0];
+        assert_eq!(
+            blueprint.materials,
+            ["ore", "clay", "diamond", "obsidian", "geode"]
+        );
+        assert_eq!(blueprint.recipe(2), [(3, 0), (2, 1), (1, 3)]);
+        // checked against a minute by minute brute force
+        assert_eq!(score(blueprint, 20), 28);
+        assert!(plan(blueprint, 20)
+            .to_string()
+            .contains("to start building a diamond-collecting robot."));
+
+        let err = parse("Blueprint 1: Each ore robot costs four ore.")
+            .err()
+            .unwrap();
+        assert_eq!((err.column, err.found.as_str()), (35, "four"));
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse(&real_input(19)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(19, 1));
+        assert_eq!(part2(&input).to_string(), answer(19, 2));
+    }
 }
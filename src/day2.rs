@@ -1,58 +1,80 @@
-#[aoc_generator(day2)]
-pub fn day2_input(input: &str) -> Vec<(u32, u32)> {
-    input
-        .lines()
-        .map(|line| {
-            let (opp, me) = line.split_once(' ').unwrap();
-            (
-                match opp {
-                    "A" => 0,
-                    "B" => 1,
-                    "C" => 2,
-                    _ => unreachable!("unexpected input"),
-                },
-                match me {
-                    "X" => 0,
-                    "Y" => 1,
-                    "Z" => 2,
-                    _ => unreachable!("unexpected input"),
-                },
-            )
-        })
-        .collect()
-}
-#[aoc(day2, part1)]
-pub fn part1(input: &[(u32, u32)]) -> u32 {
-    input
-        .iter()
-        .map(|(opp, me)| {
-            let score = me + 1;
-            // we tie
-            if me == opp {
-                return score + 3;
-            } else if (opp + 1) % 3 == *me {
-                // we win
-                return score + 6;
-            }
-            // we lose
-            score
-        })
-        .sum()
-}
-#[aoc(day2, part2)]
-pub fn part2(input: &[(u32, u32)]) -> u32 {
-    input
-        .iter()
-        .map(|(opp, me)| {
-            match me {
-                // we lose
-                0 => 1 + (opp + 2) % 3,
-                // draw
-                1 => 3 + 1 + opp,
-                // we win
-                2 => 6 + 1 + (opp + 1) % 3,
-                _ => unreachable!("unexpected"),
-            }
-        })
-        .sum()
-}
+use crate::error::{ParseError, Source};
+
+#[aoc_generator(day2)]
+pub fn day2_input(input: &str) -> Result<Vec<(u32, u32)>, ParseError> {
+    let src = Source::new(2, input);
+    input
+        .lines()
+        .map(|line| {
+            let (opp, me) = src.split_once(line, " ")?;
+            Ok((
+                match opp {
+                    "A" => 0,
+                    "B" => 1,
+                    "C" => 2,
+                    _ => return Err(src.error(opp, "A, B or C")),
+                },
+                match me {
+                    "X" => 0,
+                    "Y" => 1,
+                    "Z" => 2,
+                    _ => return Err(src.error(me, "X, Y or Z")),
+                },
+            ))
+        })
+        .collect()
+}
+#[aoc(day2, part1)]
+pub fn part1(input: &[(u32, u32)]) -> u32 {
+    input
+        .iter()
+        .map(|(opp, me)| {
+            let score = me + 1;
+            // we tie
+            if me == opp {
+                return score + 3;
+            } else if (opp + 1) % 3 == *me {
+                // we win
+                return score + 6;
+            }
+            // we lose
+            score
+        })
+        .sum()
+}
+#[aoc(day2, part2)]
+pub fn part2(input: &[(u32, u32)]) -> u32 {
+    input
+        .iter()
+        .map(|(opp, me)| {
+            match me {
+                // we lose
+                0 => 1 + (opp + 2) % 3,
+                // draw
+                1 => 3 + 1 + opp,
+                // we win
+                2 => 6 + 1 + (opp + 1) % 3,
+                _ => unreachable!("unexpected"),
+            }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day2_input(&example(2)).unwrap();
+        assert_eq!(part1(&input), 15);
+        assert_eq!(part2(&input), 12);
+    }
+    #[test]
+    fn real_answers() {
+        let input = day2_input(&real_input(2)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(2, 1));
+        assert_eq!(part2(&input).to_string(), answer(2, 2));
+    }
+}
@@ -1,11 +1,13 @@
+use crate::error::{ParseError, Source};
 use itertools::Itertools;
 
 #[aoc_generator(day20)]
-fn parse(input: &str) -> Vec<(usize, i64)> {
+fn parse(input: &str) -> Result<Vec<(usize, i64)>, ParseError> {
+    let src = Source::new(20, input);
     input
         .lines()
-        .filter_map(|l| l.parse::<i64>().ok())
         .enumerate()
+        .map(|(i, l)| Ok((i, src.parse(l, "integer")?)))
         .collect()
 }
 fn mix(markers: &[(usize, i64)], mixer: &mut Vec<(usize, i64)>, n: usize) {
@@ -41,3 +43,22 @@ fn part2(input: &[(usize, i64)]) -> i64 {
     mix(&input, &mut mixer, 10);
     mixer[1000 % len].1 + mixer[2000 % len].1 + mixer[3000 % len].1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(20)).unwrap();
+        assert_eq!(part1(&input), 3);
+        assert_eq!(part2(&input), 1623178306);
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse(&real_input(20)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(20, 1));
+        assert_eq!(part2(&input).to_string(), answer(20, 2));
+    }
+}
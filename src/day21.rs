@@ -1,3 +1,4 @@
+use crate::error::{ParseError, Source};
 use itertools::Itertools;
 use num_rational::Rational64;
 use std::collections::HashMap;
@@ -13,30 +14,33 @@ enum Expr<'a> {
     Divide(&'a str, &'a str),
 }
 
-fn parse_expr(s: &str) -> Expr {
+fn parse_expr<'a>(src: &Source, s: &'a str) -> Result<Expr<'a>, ParseError> {
     if let Ok(n) = s.parse::<Number>() {
-        return Literal(n);
+        return Ok(Literal(n));
     }
 
-    let (a, op, b) = s.split(' ').collect_tuple().unwrap();
+    let (a, op, b) = s
+        .split(' ')
+        .collect_tuple()
+        .ok_or_else(|| src.error(s, "number or monkey operation"))?;
 
     match op {
-        "+" => Add(a, b),
-        "-" => Subtract(a, b),
-        "*" => Multiply(a, b),
-        "/" => Divide(a, b),
-        _ => unreachable!("OOH OOH AHH AHH"),
+        "+" => Ok(Add(a, b)),
+        "-" => Ok(Subtract(a, b)),
+        "*" => Ok(Multiply(a, b)),
+        "/" => Ok(Divide(a, b)),
+        _ => Err(src.error(op, "+, -, * or /")),
     }
 }
 type Monkeys<'a> = HashMap<&'a str, Expr<'a>>;
 
-fn parse(input: &str) -> Monkeys {
+fn parse(input: &str) -> Result<Monkeys<'_>, ParseError> {
+    let src = Source::new(21, input);
     input
         .lines()
         .map(|l| {
-            l.split_once(": ")
-                .map(|(name, rest)| (name, parse_expr(rest)))
-                .unwrap()
+            let (name, rest) = src.split_once(l, ": ")?;
+            Ok((name, parse_expr(&src, rest)?))
         })
         .collect()
 }
@@ -63,9 +67,9 @@ fn eval1<'a>(
     }
 }
 #[aoc(day21, part1)]
-fn part1(input: &str) -> Number {
-    let monkeys = parse(input);
-    eval1(&monkeys, "root", &mut HashMap::new())
+fn part1(input: &str) -> Result<Number, ParseError> {
+    let monkeys = parse(input)?;
+    Ok(eval1(&monkeys, "root", &mut HashMap::new()))
 }
 #[derive(Debug, Copy, Clone)]
 struct Formula {
@@ -143,8 +147,8 @@ fn eval2<'a>(
     }
 }
 #[aoc(day21, part2)]
-fn part2(input: &str) -> Number {
-    let mut monkeys = parse(input);
+fn part2(input: &str) -> Result<Number, ParseError> {
+    let mut monkeys = parse(input)?;
     let (a, b) = match monkeys.get("root") {
         Some(Add(a, b)) => (a, b),
         Some(Subtract(a, b)) => (a, b),
@@ -155,5 +159,24 @@ fn part2(input: &str) -> Number {
     monkeys.insert("root", Subtract(a, b));
     monkeys.insert("humn", Unknown);
     let eq = eval2(&monkeys, "root", &mut HashMap::new());
-    -eq.b / eq.a
+    Ok(-eq.b / eq.a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = example(21);
+        assert_eq!(part1(&input).unwrap(), Number::from(152));
+        assert_eq!(part2(&input).unwrap(), Number::from(301));
+    }
+    #[test]
+    fn real_answers() {
+        let input = real_input(21);
+        assert_eq!(part1(&input).unwrap().to_string(), answer(21, 1));
+        assert_eq!(part2(&input).unwrap().to_string(), answer(21, 2));
+    }
 }
@@ -1,228 +1,638 @@
+use crate::error::{ParseError, Source};
+use crate::grid::{Grid, Pos};
 use itertools::Itertools;
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
 
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum Tile {
+pub enum Tile {
     Ground,
     Wall,
     Empty,
 }
 #[derive(Debug, Copy, Clone, PartialEq)]
-enum Action {
+pub enum Action {
     Rotate(usize),
     Move(i32),
 }
 
-struct Actions<'a>(&'a str);
-
-impl<'a> Iterator for Actions<'a> {
-    type Item = Action;
+impl Action {
+    // the action that undoes this one, after turning round
+    fn reversed(self) -> Self {
+        match self {
+            Action::Rotate(dir) => Action::Rotate((4 - dir) % 4),
+            Action::Move(steps) => Action::Move(steps),
+        }
+    }
+}
+// to get from the end of a path back to its start: turn round, undo each
+// action from the last to the first, then turn round again to face the
+// way you set off
+pub fn reverse(actions: &[Action]) -> Vec<Action> {
+    let undo = actions.iter().rev().map(|action| action.reversed());
+    [Action::Rotate(2)]
+        .into_iter()
+        .chain(undo)
+        .chain([Action::Rotate(2)])
+        .collect()
+}
+#[derive(Debug)]
+pub enum ActionError {
+    // starting `offset` bytes in, `found` is neither a number of steps
+    // that fits in an i32 nor a turn
+    Token { offset: usize, found: String },
+    Io(io::Error),
+}
+// the path as a stream of actions: numbers of steps, and L, R or U (in either
+// case) to turn left, right or round. whitespace between them is skipped
+pub struct Actions<R> {
+    reader: R,
+    // bytes read so far
+    offset: usize,
+    failed: bool,
+}
+impl<R: BufRead> Actions<R> {
+    pub fn new(reader: R) -> Self {
+        Actions {
+            reader,
+            offset: 0,
+            failed: false,
+        }
+    }
+    fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+    fn bump(&mut self) {
+        self.reader.consume(1);
+        self.offset += 1;
+    }
+    // the bytes from here up to the first one that doesn't match
+    fn take_while(&mut self, pred: impl Fn(u8) -> bool) -> io::Result<Vec<u8>> {
+        let mut token = vec![];
+        while let Some(b) = self.peek()?.filter(|&b| pred(b)) {
+            token.push(b);
+            self.bump();
+        }
+        Ok(token)
+    }
+    fn token(&mut self) -> Result<Option<Action>, ActionError> {
+        let turn = |b: u8| match b.to_ascii_uppercase() {
+            b'R' => Some(1),
+            b'U' => Some(2),
+            b'L' => Some(3),
+            _ => None,
+        };
+        self.take_while(|b| b.is_ascii_whitespace())?;
+        let start = self.offset;
+        let Some(first) = self.peek()? else {
+            return Ok(None);
+        };
+        if let Some(dir) = turn(first) {
+            self.bump();
+            return Ok(Some(Action::Rotate(dir)));
+        }
+        let (token, is_steps) = if first.is_ascii_digit() {
+            (self.take_while(|b| b.is_ascii_digit())?, true)
+        } else {
+            let junk = |b: u8| !b.is_ascii_digit() && !b.is_ascii_whitespace() && turn(b).is_none();
+            (self.take_while(junk)?, false)
+        };
+        let found = String::from_utf8_lossy(&token).into_owned();
+        match found.parse() {
+            Ok(steps) if is_steps => Ok(Some(Action::Move(steps))),
+            _ => Err(ActionError::Token {
+                offset: start,
+                found,
+            }),
+        }
+    }
+}
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ActionError::Token { offset, found } => write!(
+                f,
+                "path byte {offset}: expected a number of steps or L, R or U, found {found:?}"
+            ),
+            ActionError::Io(e) => write!(f, "reading the path: {e}"),
+        }
+    }
+}
+impl Error for ActionError {}
+impl From<io::Error> for ActionError {
+    fn from(e: io::Error) -> Self {
+        ActionError::Io(e)
+    }
+}
+impl<R: BufRead> Iterator for Actions<R> {
+    type Item = Result<Action, ActionError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.0
-            .chars()
-            .position(|c| !c.is_ascii_digit())
-            .map(|i| {
-                if i > 0 {
-                    let (steps, rest) = self.0.split_at(i);
-                    self.0 = rest;
-                    Action::Move(steps.parse().unwrap())
-                } else {
-                    let (rotate, rest) = self.0.split_at(1);
-                    self.0 = rest;
-                    match rotate {
-                        "R" => Action::Rotate(1),
-                        "L" => Action::Rotate(3),
-                        _ => unreachable!(),
-                    }
-                }
-            })
-            .or_else(|| {
-                if !self.0.is_empty() {
-                    let num = self.0.parse().unwrap();
-                    self.0 = "";
-                    Some(Action::Move(num))
-                } else {
-                    None
-                }
-            })
+        if self.failed {
+            return None;
+        }
+        // a bad token is skipped over, but the reader is given up on
+        let token = self.token();
+        self.failed = matches!(token, Err(ActionError::Io(_)));
+        token.transpose()
     }
 }
+// the path at the end of the input, pointing at anything that isn't an action
+fn parse_actions(src: &Source, path: &str) -> Result<Vec<Action>, ParseError> {
+    Actions::new(path.as_bytes())
+        .map(|action| {
+            action.map_err(|e| match e {
+                ActionError::Token { offset, found } => src.error(
+                    &path[offset..offset + found.len()],
+                    "a number of steps or L, R or U",
+                ),
+                ActionError::Io(_) => unreachable!("reading from a str can't fail"),
+            })
+        })
+        .collect()
+}
 
 const DX: &[i32; 4] = &[0, 1, 0, -1];
 const DY: &[i32; 4] = &[1, 0, -1, 0];
-fn parse_grid(input: &str) -> (Vec<Vec<Tile>>, &str) {
-    let (map, commands): (&str, &str) = input.split("\n\n").collect_tuple().unwrap();
-
-    let mut grid = map
-        .lines()
-        .map(|line| {
-            line.chars()
-                .map(|c| match c {
-                    '#' => Tile::Wall,
-                    '.' => Tile::Ground,
-                    ' ' => Tile::Empty,
-                    _ => unreachable!(),
-                })
-                .collect_vec()
-        })
-        .collect_vec();
-    // total width of the grid, max of any row's length
-    let width = grid.iter().map(|row| row.len()).max().unwrap();
-
-    for row in grid.iter_mut() {
-        row.resize_with(width, || Tile::Empty);
+// the map's text, the map itself and the path to follow
+pub fn parse_grid(input: &str) -> Result<(&str, Grid<Tile>, Vec<Action>), ParseError> {
+    let src = Source::new(22, input);
+    let (map, commands) = src.split_once(input, "\n\n")?;
+
+    // short rows get padded out with empty space
+    let grid = Grid::parse(&src, map, "#, . or space", |c| match c {
+        '#' => Some(Tile::Wall),
+        '.' => Some(Tile::Ground),
+        ' ' => Some(Tile::Empty),
+        _ => None,
+    })?;
+
+    Ok((map, grid, parse_actions(&src, commands)?))
+}
+// row, column and facing
+pub type State = (i32, i32, usize);
+const ARROWS: [char; 4] = ['>', 'v', '<', '^'];
+// every state the walker was in, turns included, from the start to the end
+pub struct Trace {
+    path: Vec<State>,
+    // what was actually done, with moves cut short by walls
+    actions: Vec<Action>,
+}
+impl Trace {
+    pub fn password(&self) -> i32 {
+        let &(x, y, facing) = self.path.last().unwrap();
+        (x + 1) * 1000 + (y + 1) * 4 + facing as i32
+    }
+    // the map with where the walker went drawn on it, then a line for
+    // every step that `label` picks out
+    fn render(&self, map: &Grid<Tile>, label: impl Fn(State, State) -> Option<String>) -> String {
+        let mut board = map.map(|tile| match tile {
+            Tile::Ground => '.',
+            Tile::Wall => '#',
+            Tile::Empty => ' ',
+        });
+        for &(x, y, facing) in &self.path {
+            board[(x as usize, y as usize)] = ARROWS[facing];
+        }
+        let mut out = String::from("\n");
+        for row in board.rows() {
+            out.extend(row);
+            out.truncate(out.trim_end().len());
+            out.push('\n');
+        }
+        for (&from, &to) in self.path.iter().tuple_windows() {
+            if let Some(label) = label(from, to) {
+                let at = |(x, y, facing): State| {
+                    format!("row {} col {} {}", x + 1, y + 1, ARROWS[facing])
+                };
+                out += &format!("\n{label}: {} to {}", at(from), at(to));
+            }
+        }
+        out
+    }
+    // walking the path backwards from the end should get back to the start
+    fn retrace(&self, map: &Grid<Tile>, surface: &impl Topology) -> String {
+        let &end = self.path.last().unwrap();
+        let back = walk(map, end, &reverse(&self.actions), surface);
+        match back.path.last().unwrap() {
+            &start if start == self.path[0] => {
+                "\n\nwalking it backwards gets back to the start".into()
+            }
+            (x, y, facing) => format!(
+                "\n\nwalking it backwards ends up at row {} col {} {} instead of the start",
+                x + 1,
+                y + 1,
+                ARROWS[*facing]
+            ),
+        }
     }
-
-    (grid, commands)
 }
-#[aoc(day22, part1)]
-fn part1(input: &str) -> i32 {
-    let (map, commands) = parse_grid(input);
-
-    let mut facing = 0;
-    let mut x = 0;
-    let mut y = map[0]
+// how the edges of the map are joined up: where taking one step from
+// (x, y) going `facing` ends up, maybe on the other side of the map
+pub trait Topology {
+    fn step(&self, x: i32, y: i32, facing: usize) -> State;
+}
+// where the path starts, facing right on the leftmost open tile of the top row
+pub fn start(map: &Grid<Tile>) -> State {
+    let col = map
+        .row(0)
         .iter()
         .position(|&tile| tile == Tile::Ground)
-        .unwrap() as i32;
-    // map direction to dx, dy
-
-    let (length, width) = (
-        map.len() as i32,
-        map.iter().map(|row| row.len()).max().unwrap() as i32,
-    );
-
-    for action in Actions(commands) {
+        .unwrap();
+    (0, col as i32, 0)
+}
+// follow the path round the map's surface
+pub fn walk(map: &Grid<Tile>, start: State, actions: &[Action], surface: &impl Topology) -> Trace {
+    let mut state = start;
+    let mut path = vec![state];
+    let mut taken = vec![];
+    for &action in actions {
         match action {
             Action::Rotate(dir) => {
-                facing = (facing + dir) % 4;
+                state.2 = (state.2 + dir) & 3;
+                path.push(state);
+                taken.push(action);
             }
             Action::Move(steps) => {
+                let mut moved = 0;
                 for _ in 0..steps {
-                    // step
-                    let mut nx = (x + DX[facing] + length) % length;
-                    let mut ny = (y + DY[facing] + width) % width;
-                    // handle wrapping over empty space
-                    // keep walking until we reach solid ground
-                    while map[nx as usize][ny as usize] == Tile::Empty {
-                        nx = (nx + DX[facing] + length) % length;
-                        ny = (ny + DY[facing] + width) % width;
-                    }
-                    // only update position if we reach solid ground
-                    if map[nx as usize][ny as usize] == Tile::Wall {
-                        break;
-                    } else {
-                        x = nx;
-                        y = ny;
+                    let next = surface.step(state.0, state.1, state.2);
+                    match map[(next.0 as usize, next.1 as usize)] {
+                        Tile::Ground => {
+                            state = next;
+                            path.push(state);
+                            moved += 1;
+                        }
+                        Tile::Wall => break,
+                        Tile::Empty => unreachable!(),
                     }
                 }
+                taken.push(Action::Move(moved));
+            }
+        }
+    }
+    Trace {
+        path,
+        actions: taken,
+    }
+}
+// off one side of the map and back on the other, skipping over empty space
+struct FlatWrap<'a>(&'a Grid<Tile>);
+impl Topology for FlatWrap<'_> {
+    fn step(&self, x: i32, y: i32, facing: usize) -> State {
+        let (length, width) = (self.0.height() as i32, self.0.width() as i32);
+        let mut nx = (x + DX[facing] + length) % length;
+        let mut ny = (y + DY[facing] + width) % width;
+        while self.0[(nx as usize, ny as usize)] == Tile::Empty {
+            nx = (nx + DX[facing] + length) % length;
+            ny = (ny + DY[facing] + width) % width;
+        }
+        (nx, ny, facing)
+    }
+}
+// like the flat wrap, except going off the top or bottom brings you back
+// on the other end mirrored left to right
+struct KleinBottle<'a>(&'a Grid<Tile>);
+impl Topology for KleinBottle<'_> {
+    fn step(&self, mut x: i32, mut y: i32, facing: usize) -> State {
+        let (length, width) = (self.0.height() as i32, self.0.width() as i32);
+        loop {
+            x += DX[facing];
+            y = (y + DY[facing] + width) % width;
+            if !(0..length).contains(&x) {
+                x = (x + length) % length;
+                y = width - 1 - y;
+            }
+            if self.0[(x as usize, y as usize)] != Tile::Empty {
+                return (x, y, facing);
             }
         }
     }
-    (x + 1) * 1000 + (y + 1) * 4 + facing as i32
+}
+// any surface at all, given which edges of the map join onto which
+#[derive(Debug)]
+struct EdgeGluing<'a> {
+    map: &'a Grid<Tile>,
+    // walking off the map or onto empty space from the key takes you to the value
+    edges: HashMap<State, State>,
+}
+impl<'a> EdgeGluing<'a> {
+    // one gluing per line, rows and columns counted from 1 like the password:
+    //   1,9 1,12 ^ = 5,4 5,1 v
+    // glues the top edge of row 1, columns 9 to 12, onto the top edge of row 5
+    // so that going up off 1,9 comes in at 5,4 going down, and so on along
+    // both edges, and back the other way too. blank lines and #s are ignored
+    fn parse(src: &Source, text: &str, map: &'a Grid<Tile>) -> Result<Self, ParseError> {
+        let in_map = |(x, y): (i32, i32)| {
+            (x >= 0 && y >= 0)
+                && map
+                    .get((x as usize, y as usize))
+                    .is_some_and(|&t| t != Tile::Empty)
+        };
+        let point = |token: &str| -> Result<(i32, i32), ParseError> {
+            let (row, col) = src.split_once(token, ",")?;
+            let pos = (
+                src.parse::<i32>(row, "row")? - 1,
+                src.parse::<i32>(col, "column")? - 1,
+            );
+            match in_map(pos) {
+                true => Ok(pos),
+                false => Err(src.error(token, "a tile on the map")),
+            }
+        };
+        let segment = |from: &str, to: &str| -> Result<Vec<(i32, i32)>, ParseError> {
+            let (start, end) = (point(from)?, point(to)?);
+            if start.0 != end.0 && start.1 != end.1 {
+                return Err(src.error(to, "the same row or column as the start"));
+            }
+            let len = start.0.abs_diff(end.0).max(start.1.abs_diff(end.1)) as i32;
+            let (dx, dy) = ((end.0 - start.0).signum(), (end.1 - start.1).signum());
+            Ok((0..=len)
+                .map(|i| (start.0 + i * dx, start.1 + i * dy))
+                .collect())
+        };
+        let facing = |token: &str| {
+            (ARROWS.iter().position(|a| token == a.to_string()))
+                .ok_or_else(|| src.error(token, "a facing, one of > v < ^"))
+        };
+
+        let mut edges = HashMap::new();
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let &[a, b, off, "=", c, d, on] = &tokens[..] else {
+                return Err(src.error(line, "a gluing like 1,9 1,12 ^ = 5,4 5,1 v"));
+            };
+            let (from, off) = (segment(a, b)?, facing(off)?);
+            let (to, on) = (segment(c, d)?, facing(on)?);
+            if from.len() != to.len() {
+                return Err(src.error(d, "an edge as long as the one it's glued to"));
+            }
+            for (&(x, y), &(nx, ny)) in from.iter().zip(&to) {
+                edges.insert((x, y, off), (nx, ny, on));
+                edges.insert((nx, ny, (on + 2) % 4), (x, y, (off + 2) % 4));
+            }
+        }
+
+        // every way off the map has to lead somewhere
+        for ((x, y), &tile) in map.iter() {
+            let (x, y) = (x as i32, y as i32);
+            for facing in 0..4 {
+                let off_edge = tile != Tile::Empty && !in_map((x + DX[facing], y + DY[facing]));
+                if off_edge && !edges.contains_key(&(x, y, facing)) {
+                    let (row, col, arrow) = (x + 1, y + 1, ARROWS[facing]);
+                    return Err(src.missing(&format!("a gluing for going {arrow} off {row},{col}")));
+                }
+            }
+        }
+        Ok(Self { map, edges })
+    }
+}
+impl Topology for EdgeGluing<'_> {
+    fn step(&self, x: i32, y: i32, facing: usize) -> State {
+        let (nx, ny) = (x + DX[facing], y + DY[facing]);
+        let on_map = nx >= 0
+            && ny >= 0
+            && self
+                .map
+                .get((nx as usize, ny as usize))
+                .is_some_and(|&t| t != Tile::Empty);
+        match on_map {
+            true => (nx, ny, facing),
+            false => self.edges[&(x, y, facing)],
+        }
+    }
+}
+// the password for walking the input's map and path on a surface described
+// by a gluing file, see `EdgeGluing::parse` for the format
+pub fn walk_glued(input: &str, gluing: &Path) -> Result<i32, Box<dyn Error>> {
+    let (_, map, actions) = parse_grid(input)?;
+    let text = fs::read_to_string(gluing)?;
+    let file = format!("gluing file {}", gluing.display());
+    let surface = EdgeGluing::parse(&Source::file(22, &file, &text), &text, &map)?;
+    Ok(walk(&map, start(&map), &actions, &surface).password())
+}
+fn trace_flat(input: &str) -> Result<(Grid<Tile>, Trace), ParseError> {
+    let (_, map, actions) = parse_grid(input)?;
+    let trace = walk(&map, start(&map), &actions, &FlatWrap(&map));
+    Ok((map, trace))
+}
+#[aoc(day22, part1)]
+fn part1(input: &str) -> Result<i32, ParseError> {
+    Ok(trace_flat(input)?.1.password())
+}
+// the same walk with the top and bottom of the map glued on back to front
+#[aoc(day22, part1, klein)]
+fn part1_klein(input: &str) -> Result<i32, ParseError> {
+    let (_, map, actions) = parse_grid(input)?;
+    Ok(walk(&map, start(&map), &actions, &KleinBottle(&map)).password())
+}
+#[aoc(day22, part1, trace)]
+fn part1_trace(input: &str) -> Result<String, ParseError> {
+    let (map, trace) = trace_flat(input)?;
+    let out = trace.render(&map, |(x, y, facing), to| {
+        // turning on the spot isn't a wrap
+        let moved = (x, y) != (to.0, to.1);
+        let straight = (x + DX[facing], y + DY[facing], facing);
+        (moved && straight != to).then(|| "wrapped".to_string())
+    });
+    Ok(out + &trace.retrace(&map, &FlatWrap(&map)))
+}
+// 3d directions for folding up the cube
+type Vec3 = [i32; 3];
+fn neg([x, y, z]: Vec3) -> Vec3 {
+    [-x, -y, -z]
+}
+// where a face of the net ends up once it's folded: which way its rows and
+// columns run and which way it looks out of the cube
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Orientation {
+    right: Vec3,
+    down: Vec3,
+    normal: Vec3,
+}
+impl Orientation {
+    const FLAT: Self = Orientation {
+        right: [1, 0, 0],
+        down: [0, 1, 0],
+        normal: [0, 0, 1],
+    };
+    // the face next door in direction dir, folded down away from us
+    fn roll(self, dir: usize) -> Self {
+        let Orientation {
+            right,
+            down,
+            normal,
+        } = self;
+        match dir {
+            0 => Orientation {
+                right: neg(normal),
+                down,
+                normal: right,
+            },
+            1 => Orientation {
+                right,
+                down: neg(normal),
+                normal: down,
+            },
+            2 => Orientation {
+                right: normal,
+                down,
+                normal: neg(right),
+            },
+            _ => Orientation {
+                right,
+                down: normal,
+                normal: neg(down),
+            },
+        }
+    }
+    // the way a facing on the map points once folded
+    fn heading(self, dir: usize) -> Vec3 {
+        [self.right, self.down, neg(self.right), neg(self.down)][dir]
+    }
+}
+// an error pointing at a cell of the map, or just past the end of its row
+fn cell_error(src: &Source, map: &str, (row, col): Pos, expected: &str) -> ParseError {
+    let line = map.lines().nth(row).unwrap_or(&map[map.len()..]);
+    match line.char_indices().nth(col) {
+        Some((i, _)) => src.char_error(line, i, expected),
+        None => src.error(&line[line.len()..], expected),
+    }
 }
 #[derive(Debug)]
 struct Cube {
     side_length: i32,
-    map: Vec<Vec<Tile>>,
+    map: Grid<Tile>,
+    // transitions[i][dir] is the face and facing you end up with by
+    // walking off the ith face going dir
     face_transitions: [[(usize, usize); 4]; 6],
+    // which face is where on the net, in units of side_length
     face_id: HashMap<(i32, i32), usize>,
-}
-// id of face
-// basically the 50x50 area it is in
-fn face(x: i32, y: i32, side_length: i32) -> (i32, i32) {
-    (
-        (x + side_length) / side_length,
-        (y + side_length) / side_length,
-    )
+    corners: Vec<(i32, i32)>,
 }
 impl Cube {
-    fn new(map: Vec<Vec<Tile>>) -> Self {
-        // compute dimension of each face
-        let face_area = map
-            .iter()
-            .flat_map(|row| row.iter().filter(|&t| *t != Tile::Empty))
-            .count()
-            / 6;
-
-        let side_length = (face_area as f64).sqrt() as u32;
-        // map from face id to face number
-        let mut face_id = HashMap::new();
-        for (row_id, row) in map.iter().enumerate() {
-            for (col_id, cell) in row.iter().enumerate() {
-                if cell != &Tile::Empty {
-                    let id = face(row_id as i32, col_id as i32, side_length as i32);
-                    let n = face_id.len();
-                    face_id.entry(id).or_insert_with(|| n);
-                }
-            }
+    // folds any of the 11 cube nets, whichever way round it's drawn
+    fn new(src: &Source, net: &str, map: Grid<Tile>) -> Result<Self, ParseError> {
+        let not_empty = |pos: Pos| map.get(pos).is_some_and(|&t| t != Tile::Empty);
+        let area = map.iter().filter(|&(_, t)| *t != Tile::Empty).count();
+        let side = (1..).find(|s| 6 * s * s >= area).unwrap();
+        let first_line = net.lines().next().unwrap_or(net);
+        if 6 * side * side != area {
+            return Err(src.error(first_line, "a map made of six square faces"));
         }
-        // build transitions from each face
-        // transitions[i][dir] will be the face and direction you get to by going "dir"
-        // on the ith face
-        let mut transitions = [[None; 4]; 6];
-        // build trivial transitions
-        // ones that are apparent from input
-        for (&(x, y), &num) in face_id.iter() {
-            for dir in 0..4 {
-                let nx = x + DX[dir];
-                let ny = y + DY[dir];
 
-                if let Some(&id) = face_id.get(&(nx, ny)) {
-                    transitions[num][dir] = Some((id, dir));
+        // every side x side block of the map is either a whole face or empty
+        let mut corners = vec![];
+        for face_row in 0..map.height().div_ceil(side) {
+            for face_col in 0..map.width().div_ceil(side) {
+                let top_left = (face_row * side, face_col * side);
+                let cells =
+                    (0..side * side).map(|i| (top_left.0 + i / side, top_left.1 + i % side));
+                let mut cells = cells.map(|pos| (pos, not_empty(pos)));
+                let is_face = not_empty(top_left);
+                if let Some((pos, _)) = cells.find(|&(_, tile)| tile != is_face) {
+                    let expected = if is_face {
+                        "a tile to finish the face"
+                    } else {
+                        "empty space, faces line up on a grid"
+                    };
+                    return Err(cell_error(src, net, pos, expected));
+                }
+                if is_face {
+                    corners.push((face_row as i32, face_col as i32));
                 }
             }
         }
-        // build other transitions
-        // we use the fact that
-        // going left and forward, then right and forward, then left
-        // brings you to the same face and direction as just going forward
-        let mut need_more = true;
-        while need_more {
-            for face in 0..6 {
-                for dir in 0..4 {
-                    if transitions[face][dir].is_some() {
-                        continue;
-                    }
-
-                    let left = (dir + 3) & 3;
-
-                    if let Some((left_face, left_facing)) = transitions[face][left] {
-                        let right = (left_facing + 1) & 3;
+        let face_id: HashMap<(i32, i32), usize> =
+            corners.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+        let corner_error = |face: usize, expected: &str| {
+            let (row, col) = corners[face];
+            cell_error(
+                src,
+                net,
+                (row as usize * side, col as usize * side),
+                expected,
+            )
+        };
 
-                        if let Some((right_face, right_facing)) = transitions[left_face][right] {
-                            let left = (right_facing + 3) & 3;
-                            transitions[face][dir] = Some((right_face, left));
-                        }
+        // fold the net up starting from the first face, rolling over
+        // each edge onto the faces next to it
+        let mut orientation = vec![None; 6];
+        orientation[0] = Some(Orientation::FLAT);
+        let mut queue = vec![0];
+        while let Some(face) = queue.pop() {
+            let (row, col) = corners[face];
+            let here = orientation[face].unwrap();
+            for dir in 0..4 {
+                let next = (row + DX[dir], col + DY[dir]);
+                if let Some(&next) = face_id.get(&next) {
+                    if orientation[next].is_none() {
+                        orientation[next] = Some(here.roll(dir));
+                        queue.push(next);
                     }
                 }
             }
-            need_more = transitions.iter().flatten().any(|i| i.is_none());
         }
-        // have to do this the hard way bc can't collect into a slice
-        let face_transitions = {
-            let mut target = [[(0, 0); 4]; 6];
-            for face in 0..6 {
-                for dir in 0..4 {
-                    target[face][dir] = transitions[face][dir].unwrap();
-                }
+        if let Some(face) = orientation.iter().position(Option::is_none) {
+            return Err(corner_error(face, "a face joined onto the rest of the net"));
+        }
+        let orientation: Vec<Orientation> = orientation.into_iter().flatten().collect();
+        for (face, o) in orientation.iter().enumerate() {
+            if orientation[..face]
+                .iter()
+                .any(|other| other.normal == o.normal)
+            {
+                return Err(corner_error(face, "a face that doesn't fold onto another"));
             }
-            target
-        };
+        }
+
+        // walking off an edge takes you onto the face that looks the way you
+        // were heading, and once over the edge you're heading into the cube
+        let mut face_transitions = [[(0, 0); 4]; 6];
+        for (o, transitions) in orientation.iter().zip(&mut face_transitions) {
+            for (dir, transition) in transitions.iter_mut().enumerate() {
+                let heading = o.heading(dir);
+                let next = orientation
+                    .iter()
+                    .position(|n| n.normal == heading)
+                    .unwrap();
+                let next_dir = (0..4)
+                    .find(|&d| orientation[next].heading(d) == neg(o.normal))
+                    .unwrap();
+                *transition = (next, next_dir);
+            }
+        }
 
-        Self {
-            side_length: side_length as i32,
+        Ok(Self {
+            side_length: side as i32,
             map,
             face_transitions,
             face_id,
-        }
+            corners,
+        })
     }
 
     fn face(&self, x: i32, y: i32) -> (i32, i32) {
-        face(x, y, self.side_length)
+        (
+            x.div_euclid(self.side_length),
+            y.div_euclid(self.side_length),
+        )
     }
 
+    // 1 based, in the order the faces appear on the net
+    fn face_number(&self, x: i32, y: i32) -> usize {
+        self.face_id[&self.face(x, y)] + 1
+    }
+}
+impl Topology for Cube {
     fn step(&self, x: i32, y: i32, dir: usize) -> (i32, i32, usize) {
         let start_face = self.face(x, y);
         let nx = x + DX[dir];
@@ -250,15 +660,9 @@ impl Cube {
                 localy = ny;
             }
 
-            let &(x_id, y_id) = self
-                .face_id
-                .iter()
-                .find(|&(_, y)| *y == end_faceid)
-                .unwrap()
-                .0;
-
-            let x_id = (x_id - 1) * self.side_length;
-            let y_id = (y_id - 1) * self.side_length;
+            let (x_id, y_id) = self.corners[end_faceid];
+            let x_id = x_id * self.side_length;
+            let y_id = y_id * self.side_length;
 
             (
                 x_id + localx + (1 - self.side_length) * DX[end_dir],
@@ -267,50 +671,262 @@ impl Cube {
             )
         }
     }
-
-    fn get(&self, x: i32, y: i32) -> Tile {
-        self.map[x as usize][y as usize]
-    }
 }
 
+fn trace_cube(input: &str) -> Result<(Cube, Trace), ParseError> {
+    let (net, map, actions) = parse_grid(input)?;
+    let cube = Cube::new(&Source::new(22, input), net, map)?;
+    let trace = walk(&cube.map, start(&cube.map), &actions, &cube);
+    Ok((cube, trace))
+}
 #[aoc(day22, part2)]
-fn part2(input: &str) -> i32 {
-    let (map, commands) = parse_grid(input);
-
-    let mut facing = 0;
-    let mut x = 0;
-    let mut y = map[0]
-        .iter()
-        .position(|&tile| tile == Tile::Ground)
-        .unwrap() as i32;
-    // map direction to dx, dy
-
-    let width = map.iter().map(|row| row.len()).max().unwrap() as i32;
+fn part2(input: &str) -> Result<i32, ParseError> {
+    Ok(trace_cube(input)?.1.password())
+}
+// faces are numbered in reading order on the net, like the puzzle's example
+#[aoc(day22, part2, trace)]
+fn part2_trace(input: &str) -> Result<String, ParseError> {
+    let (cube, trace) = trace_cube(input)?;
+    let out = trace.render(&cube.map, |(x, y, _), (nx, ny, _)| {
+        let (from, to) = (cube.face_number(x, y), cube.face_number(nx, ny));
+        (from != to).then(|| format!("face {from} -> face {to}"))
+    });
+    Ok(out + &trace.retrace(&cube.map, &cube))
+}
 
-    let cube = Cube::new(map);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
 
-    for action in Actions(commands) {
-        match action {
-            Action::Rotate(dir) => {
-                facing = (facing + dir) & 3;
+    #[test]
+    fn example_answers() {
+        let input = example(22);
+        assert_eq!(part1(&input).unwrap(), 6032);
+        assert_eq!(part2(&input).unwrap(), 5031);
+    }
+    #[test]
+    fn example_traces() {
+        let input = example(22);
+        let flat = part1_trace(&input).unwrap();
+        assert!(flat.contains("\n>>>v...>#.>>\n"));
+        assert!(flat.contains("\nwrapped: row 6 col 12 > to row 6 col 1 >\n"));
+        assert_eq!(flat.matches("wrapped").count(), 2);
+
+        // the same walk as the puzzle's example
+        let cube = part2_trace(&input).unwrap();
+        assert!(cube.starts_with("\n        >>v#\n"));
+        assert!(cube.contains("\n.>>>>>^.#.>>\n"));
+        assert!(cube.contains("\nface 4 -> face 6: row 6 col 12 > to row 9 col 15 v\n"));
+        assert!(cube.contains("face 2 -> face 3: row 6 col 4 > to row 6 col 5 >\n"));
+        assert!(cube.ends_with("\nwalking it backwards gets back to the start"));
+        assert!(flat.ends_with("\nwalking it backwards gets back to the start"));
+    }
+    #[test]
+    fn action_tokens() {
+        use std::io::{BufReader, Cursor};
+        let actions = |path: &str| Actions::new(path.as_bytes()).collect::<Vec<_>>();
+        let all = actions("10R5l2Uu 3\n");
+        let all: Vec<Action> = all.into_iter().map(Result::unwrap).collect();
+        use Action::{Move, Rotate};
+        let expected = [
+            Move(10),
+            Rotate(1),
+            Move(5),
+            Rotate(3),
+            Move(2),
+            Rotate(2),
+            Rotate(2),
+            Move(3),
+        ];
+        assert_eq!(all, expected);
+
+        // bad tokens are reported with where they start and skipped over
+        let bad = actions("10Xé5R99999999999");
+        let errors: Vec<(usize, String)> = (bad.iter())
+            .filter_map(|action| match action {
+                Err(ActionError::Token { offset, found }) => Some((*offset, found.clone())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(errors, [(2, "Xé".into()), (7, "99999999999".into())]);
+        assert_eq!(bad.len(), 5);
+        let input = example(22).replace("10R5L5R10L4R5L5", "10R5L5R10Q4R5L5");
+        let error = part1(&input).unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.found.as_str()),
+            (14, 10, "Q")
+        );
+
+        // a reader gets read a bit at a time, the same as a str all at once
+        let path = "10R5L5R10L4R5L5\n";
+        let streamed = Actions::new(BufReader::with_capacity(3, Cursor::new(path)));
+        let streamed: Vec<Action> = streamed.map(Result::unwrap).collect();
+        let (_, map, actions) = parse_grid(&example(22)).unwrap();
+        assert_eq!(streamed, actions);
+
+        // walking a path backwards from where it ends gets back to the start
+        let forward = walk(&map, start(&map), &actions, &FlatWrap(&map));
+        let &end = forward.path.last().unwrap();
+        let back = walk(&map, end, &reverse(&forward.actions), &FlatWrap(&map));
+        assert_eq!(back.path.last(), forward.path.first());
+        assert_eq!(back.path.len(), forward.path.len() + 2);
+    }
+    #[test]
+    fn other_surfaces() {
+        let input = example(22);
+        let table = format!(
+            "{}/input/2022/example22_cube.txt",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        assert_eq!(walk_glued(&input, Path::new(&table)).unwrap(), 5031);
+
+        // the gluing table is the same surface as the folded cube
+        let (net, map, _) = parse_grid(&input).unwrap();
+        let text = std::fs::read_to_string(&table).unwrap();
+        let glued = EdgeGluing::parse(&Source::new(22, &text), &text, &map).unwrap();
+        let cube = Cube::new(&Source::new(22, &input), net, map.clone()).unwrap();
+        for ((x, y), &tile) in map.iter() {
+            for facing in 0..4 {
+                if tile != Tile::Empty {
+                    let (x, y) = (x as i32, y as i32);
+                    assert_eq!(glued.step(x, y, facing), cube.step(x, y, facing));
+                }
             }
-            Action::Move(steps) => {
-                for _ in 0..steps {
-                    let (nx, ny, next_dir) = cube.step(x, y, facing);
-                    match cube.get(nx, ny) {
-                        Tile::Ground => {
-                            x = nx;
-                            y = ny;
-                            facing = next_dir;
-                        }
-                        Tile::Wall => {
-                            break;
+        }
+        // leaving out a gluing leaves a way off the map
+        let missing = text.replace("1,12 4,12 > = 12,16 9,16 <", "");
+        let error = EdgeGluing::parse(&Source::new(22, &missing), &missing, &map).unwrap_err();
+        assert_eq!(error.expected, "a gluing for going > off 1,12");
+        let bad = std::env::temp_dir().join(format!("day22-gluing-{}.txt", std::process::id()));
+        std::fs::write(&bad, &missing).unwrap();
+        let error = walk_glued(&input, &bad).unwrap_err().to_string();
+        std::fs::remove_file(&bad).unwrap();
+        assert!(error.starts_with(&format!("day 22 gluing file {}, line ", bad.display())));
+        let bent = "1,9 2,10 ^ = 5,4 5,1 v";
+        let error = EdgeGluing::parse(&Source::new(22, bent), bent, &map).unwrap_err();
+        assert_eq!((error.column, error.found.as_str()), (5, "2,10"));
+
+        // on a klein bottle going down off the bottom comes back in at
+        // the top on the other side, so it takes twice as long to get back
+        let (_, map, _) = parse_grid("....\n....\n....\n\n1").unwrap();
+        let klein = KleinBottle(&map);
+        let mut at = (0, 0, 1);
+        for _ in 0..3 {
+            at = klein.step(at.0, at.1, at.2);
+        }
+        assert_eq!(at, (0, 3, 1));
+        for _ in 0..3 {
+            at = klein.step(at.0, at.1, at.2);
+        }
+        assert_eq!(at, (0, 0, 1));
+        assert_eq!(klein.step(1, 3, 0), (1, 0, 0));
+        assert!(part1_klein(&input).is_ok());
+
+        // any surface can be walked, this one has no edges to go off
+        struct Torus;
+        impl Topology for Torus {
+            fn step(&self, x: i32, y: i32, facing: usize) -> State {
+                (
+                    (x + DX[facing]).rem_euclid(3),
+                    (y + DY[facing]).rem_euclid(4),
+                    facing,
+                )
+            }
+        }
+        let (_, map, actions) = parse_grid("....\n..#.\n....\n\n5R2").unwrap();
+        let trace = walk(&map, start(&map), &actions, &Torus);
+        assert_eq!(trace.password(), 3000 + 8 + 1);
+    }
+    // the 11 nets, one character per face
+    const NETS: [&str; 11] = [
+        "#...\n####\n#...",
+        "#...\n####\n.#..",
+        "#...\n####\n..#.",
+        "#...\n####\n...#",
+        ".#..\n####\n.#..",
+        ".#..\n####\n..#.",
+        "##..\n.###\n.#..",
+        "##..\n.###\n..#.",
+        "##..\n.###\n...#",
+        "##..\n.##.\n..##",
+        "###..\n..###",
+    ];
+    // every rotation and reflection of a net, blown up to faces of `side`
+    fn layouts(net: &str, side: usize) -> Vec<String> {
+        let mut cells: Vec<Vec<char>> = net.lines().map(|l| l.chars().collect()).collect();
+        let mut layouts = vec![];
+        for _ in 0..4 {
+            let mirrored = cells.iter().map(|row| row.iter().rev().copied().collect());
+            for shape in [cells.clone(), mirrored.collect()] {
+                let rows = shape.iter().flat_map(|row| {
+                    let line: String = (row.iter())
+                        .flat_map(|&c| std::iter::repeat_n(if c == '#' { '.' } else { ' ' }, side))
+                        .collect();
+                    std::iter::repeat_n(line, side)
+                });
+                layouts.push(rows.collect::<Vec<_>>().join("\n"));
+            }
+            // rotate a quarter turn clockwise
+            cells = (0..cells[0].len())
+                .map(|c| cells.iter().rev().map(|row| row[c]).collect())
+                .collect();
+        }
+        layouts
+    }
+    fn fold(layout: &str) -> Result<Cube, ParseError> {
+        let input = format!("{layout}\n\n1");
+        let (net, map, _) = parse_grid(&input)?;
+        Cube::new(&Source::new(22, &input), net, map)
+    }
+    #[test]
+    fn every_net_folds() {
+        for net in NETS {
+            for layout in layouts(net, 3) {
+                let cube = fold(&layout).unwrap();
+                // going four sides round the cube in a straight line gets
+                // you back where you started, facing the same way
+                for ((x, y), &tile) in cube.map.iter() {
+                    if tile == Tile::Empty {
+                        continue;
+                    }
+                    for dir in 0..4 {
+                        let start = (x as i32, y as i32, dir);
+                        let mut at = start;
+                        for _ in 0..4 * cube.side_length {
+                            let (x, y, dir) = cube.step(at.0, at.1, at.2);
+                            assert_eq!(
+                                cube.map[(x as usize, y as usize)],
+                                Tile::Ground,
+                                "{layout}"
+                            );
+                            // and turning round takes you straight back
+                            let (bx, by, bdir) = cube.step(x, y, (dir + 2) % 4);
+                            assert_eq!((bx, by, (bdir + 2) % 4), at, "{layout}");
+                            at = (x, y, dir);
                         }
-                        Tile::Empty => unreachable!(),
+                        assert_eq!(at, start, "{layout}");
                     }
                 }
             }
         }
     }
-    (x + 1) * 1000 + (y + 1) * 4 + facing as i32
+    #[test]
+    fn unfoldable_maps() {
+        let error = |layout: &str| fold(layout).unwrap_err().to_string();
+        // six faces in a row wrap round onto each other
+        assert!(error("......").contains("line 1, column 5"));
+        assert!(error("...\n...").contains("a face that doesn't fold onto another"));
+        // a face knocked out of line with the rest
+        let shifted = "..\n..\n........\n........\n ..\n ..";
+        assert!(error(shifted).contains("line 5, column 2"));
+        assert!(error(". . \n  . .\n. . ").contains("a face joined onto the rest of the net"));
+        assert!(error("....\n...").contains("a map made of six square faces"));
+    }
+    #[test]
+    fn real_answers() {
+        let input = real_input(22);
+        assert_eq!(part1(&input).unwrap().to_string(), answer(22, 1));
+        assert_eq!(part2(&input).unwrap().to_string(), answer(22, 2));
+    }
 }
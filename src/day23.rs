@@ -1,50 +1,37 @@
-use fxhash::FxHashSet as HashSet;
+use crate::error::{ParseError, Source};
+use crate::grid::{Grid, Point, SparseGrid};
 use itertools::Itertools;
 
-type Pos = (i32, i32);
-const SURROUNDING: [Pos; 8] = [
-    (1, 1),
-    (1, 0),
-    (1, -1),
-    (0, 1),
-    (0, -1),
-    (-1, 1),
-    (-1, 0),
-    (-1, -1),
-];
+// (row, col) of every elf
+type Elves = SparseGrid<()>;
 
-const DIRECTIONS: [[Pos; 3]; 4] = [
-    [(1, -1), (0, -1), (-1, -1)],
-    [(1, 1), (0, 1), (-1, 1)],
+// north, south, west, east, each with the move in the middle
+const DIRECTIONS: [[Point; 3]; 4] = [
     [(-1, 1), (-1, 0), (-1, -1)],
     [(1, 1), (1, 0), (1, -1)],
+    [(1, -1), (0, -1), (-1, -1)],
+    [(1, 1), (0, 1), (-1, 1)],
 ];
 
 #[aoc_generator(day23)]
-fn parse(input: &str) -> HashSet<Pos> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, l)| {
-            l.bytes()
-                .enumerate()
-                .filter(|&(_, c)| c == b'#')
-                .map(move |(x, _)| (x as i32, y as i32))
-        })
-        .collect()
+fn parse(input: &str) -> Result<Elves, ParseError> {
+    let src = Source::new(23, input);
+    let grid = Grid::parse(&src, input, "# or .", |c| match c {
+        '#' => Some(true),
+        '.' | ' ' => Some(false),
+        _ => None,
+    })?;
+    Ok(grid.to_sparse(|&elf| elf.then_some(())))
 }
-fn destination(elf: Pos, step: usize, elves: &HashSet<Pos>) -> Pos {
-    if SURROUNDING
-        .iter()
-        .any(|dir| elves.contains(&(elf.0 + dir.0, elf.1 + dir.1)))
-    {
+fn destination(elf: Point, step: usize, elves: &Elves) -> Point {
+    if elves.neighbors8(elf).any(|p| elves.contains(p)) {
         for dir in step..step + 4 {
             let dirs = DIRECTIONS[dir & 3];
 
             // move if no elves
             if !dirs
                 .iter()
-                .any(|d| elves.contains(&(elf.0 + d.0, elf.1 + d.1)))
+                .any(|d| elves.contains((elf.0 + d.0, elf.1 + d.1)))
             {
                 return (elf.0 + dirs[1].0, elf.1 + dirs[1].1);
             }
@@ -53,62 +40,49 @@ fn destination(elf: Pos, step: usize, elves: &HashSet<Pos>) -> Pos {
     elf
 }
 #[aoc(day23, part1)]
-fn part1(input: &HashSet<Pos>) -> usize {
+fn part1(input: &Elves) -> usize {
     let mut elves = input.clone();
 
     for round in 0..10 {
-        let mut new_elves: HashSet<Pos> =
-            HashSet::with_capacity_and_hasher(elves.capacity(), Default::default());
+        let mut new_elves: Elves = SparseGrid::with_capacity(elves.capacity());
 
-        for &elf in elves.iter() {
+        for elf in elves.points() {
             let new_pos = destination(elf, round, &elves);
             if new_pos == elf {
-                new_elves.insert(elf);
-            } else if !new_elves.insert(new_pos) {
-                new_elves.remove(&new_pos);
-                new_elves.insert(elf);
+                new_elves.insert(elf, ());
+            } else if new_elves.insert(new_pos, ()).is_some() {
+                new_elves.remove(new_pos);
+                new_elves.insert(elf, ());
                 // conflict must come from opposite direction
-                new_elves.insert((new_pos.0 * 2 - elf.0, new_pos.1 * 2 - elf.1));
+                new_elves.insert((new_pos.0 * 2 - elf.0, new_pos.1 * 2 - elf.1), ());
             }
         }
         elves = new_elves;
     }
-    let (minx, maxx) = elves
-        .iter()
-        .map(|(x, _)| *x)
-        .minmax()
-        .into_option()
-        .unwrap();
-    let (miny, maxy) = elves
-        .iter()
-        .map(|(_, y)| *y)
-        .minmax()
-        .into_option()
-        .unwrap();
+    let ((minr, minc), (maxr, maxc)) = elves.bounds().unwrap();
 
-    (minx..=maxx)
-        .cartesian_product(miny..=maxy)
-        .filter(|p| !elves.contains(p))
+    (minr..=maxr)
+        .cartesian_product(minc..=maxc)
+        .filter(|&p| !elves.contains(p))
         .count()
 }
 #[aoc(day23, part2)]
-fn part2(input: &HashSet<Pos>) -> usize {
+fn part2(input: &Elves) -> usize {
     let mut elves = input.clone();
 
     for round in 0.. {
-        let mut new_elves: HashSet<Pos> =
-            HashSet::with_capacity_and_hasher(elves.capacity(), Default::default());
+        let mut new_elves: Elves = SparseGrid::with_capacity(elves.capacity());
 
         let mut num_moves = 0;
-        for &elf in elves.iter() {
+        for elf in elves.points() {
             let new_pos = destination(elf, round, &elves);
             if new_pos == elf {
-                new_elves.insert(elf);
-            } else if !new_elves.insert(new_pos) {
-                new_elves.remove(&new_pos);
-                new_elves.insert(elf);
+                new_elves.insert(elf, ());
+            } else if new_elves.insert(new_pos, ()).is_some() {
+                new_elves.remove(new_pos);
+                new_elves.insert(elf, ());
                 // conflict must come from opposite direction
-                new_elves.insert((new_pos.0 * 2 - elf.0, new_pos.1 * 2 - elf.1));
+                new_elves.insert((new_pos.0 * 2 - elf.0, new_pos.1 * 2 - elf.1), ());
                 num_moves -= 1;
             } else {
                 num_moves += 1;
@@ -121,3 +95,22 @@ fn part2(input: &HashSet<Pos>) -> usize {
     }
     0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(23)).unwrap();
+        assert_eq!(part1(&input), 110);
+        assert_eq!(part2(&input), 20);
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse(&real_input(23)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(23, 1));
+        assert_eq!(part2(&input).to_string(), answer(23, 2));
+    }
+}
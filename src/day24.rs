@@ -1,128 +1,163 @@
-use fxhash::FxHashSet as Hashset;
-use std::collections::HashSet;
+use crate::error::{ParseError, Source};
+use crate::grid::{Grid, Pos};
+use crate::search::{self, Graph};
 
-const START: (usize, usize) = (0, 1);
-const END: (usize, usize) = (26, 120);
-const COLS: usize = 122;
-const ROWS: usize = 27;
-#[aoc_generator(day24)]
-fn parse(input: &str) -> Vec<(usize, usize, char)> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(r, row)| {
-            row.chars()
-                .enumerate()
-                .filter_map(move |(c, b)| "<^>v".contains(b).then_some((r, c, b)))
-        })
-        .collect()
+#[derive(Debug, Clone)]
+struct Valley {
+    // true for the walls around the edge
+    walls: Grid<bool>,
+    entrance: Pos,
+    exit: Pos,
+    // where each blizzard starts, '.' for none
+    blizzards: Grid<char>,
+    // the blizzards are back where they started after this many minutes
+    period: usize,
 }
-fn update_positions(
-    positions: Hashset<(usize, usize)>,
-    blizzards: &mut Vec<(usize, usize, char)>,
-) -> Hashset<(usize, usize)> {
-    // update blizzard positions
-    for (row, col, c) in blizzards.iter_mut() {
-        match c {
-            '<' => {
-                if *col != 1 {
-                    *col -= 1;
-                } else {
-                    // wrap around
-                    *col = COLS - 2;
-                }
-            }
-            '>' => {
-                if *col != COLS - 2 {
-                    *col += 1;
-                } else {
-                    *col = 1;
-                }
-            }
-            '^' => {
-                if *row != 1 {
-                    *row -= 1;
-                } else {
-                    *row = ROWS - 2;
-                }
-            }
-            'v' => {
-                if *row != ROWS - 2 {
-                    *row += 1;
-                } else {
-                    *row = 1;
-                }
-            }
-            _ => unreachable!("Bad blizzard"),
+impl Valley {
+    // blizzards move in straight lines, so rather than simulating them we
+    // look back along each row and column for one that would be here now
+    fn blizzard_at(&self, (row, col): Pos, time: usize) -> bool {
+        let (width, height) = (self.walls.width() - 2, self.walls.height() - 2);
+        if row == 0 || row > height {
+            return false;
         }
+        let (r, c) = (row - 1, col - 1);
+        let start = |r: usize, c: usize| self.blizzards[(r + 1, c + 1)];
+        start(r, (c + width - time % width) % width) == '>'
+            || start(r, (c + time) % width) == '<'
+            || start((r + height - time % height) % height, c) == 'v'
+            || start((r + time) % height, c) == '^'
     }
-    // faster contains method
-    let blizzard_pos = blizzards
-        .iter()
-        .map(|&(x, y, _)| (x, y))
-        .collect::<Hashset<_>>();
-    // faster than updating in place
-    let mut new_pos = Hashset::with_capacity_and_hasher(positions.len(), Default::default());
-
-    for &(x, y) in &positions {
-        for (dx, dy) in [(1, 0), (0, 1), (0, 0), (-1, 0), (0, -1)] {
-            if x as i32 + dx < 0 || x as i32 + dx >= ROWS as i32 {
-                continue;
-            }
-            let (x, y) = ((x as i32 + dx) as usize, (y as i32 + dy) as usize);
+    // fewest minutes to get from `from` to `to`, setting off at `time`
+    fn crossing(&self, from: Pos, to: Pos, time: usize) -> usize {
+        let manhattan = |(r, c): Pos| r.abs_diff(to.0) + c.abs_diff(to.1);
+        search::astar(
+            self,
+            [(from, time % self.period)],
+            |&(pos, _)| pos == to,
+            |&(pos, _)| manhattan(pos),
+        )
+        .expect("no way across the valley")
+        .cost
+    }
+}
+// (position, minute mod period), the blizzards look the same every period
+impl Graph for Valley {
+    type Node = (Pos, usize);
 
-            if valid_pos(x, y) && !blizzard_pos.contains(&(x, y)) {
-                new_pos.insert((x, y));
-            }
-        }
+    fn neighbors(
+        &self,
+        &(pos, time): &(Pos, usize),
+    ) -> impl Iterator<Item = ((Pos, usize), usize)> {
+        let time = (time + 1) % self.period;
+        // waiting in place is always an option
+        self.walls
+            .neighbors4(pos)
+            .chain([pos])
+            .filter(move |&next| !self.walls[next] && !self.blizzard_at(next, time))
+            .map(move |next| ((next, time), 1))
     }
-    new_pos
 }
-fn valid_pos(x: usize, y: usize) -> bool {
-    (x != 0 || y == 1) && (x != ROWS - 1 || y == COLS - 2) && y != 0 && y != COLS - 1
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
-#[aoc(day24, part1)]
-fn part1(input: &[(usize, usize, char)]) -> usize {
-    let mut blizzards = input.to_vec();
-    let mut positions: Hashset<(usize, usize)> = Hashset::from_iter([(0, 1)]);
-
-    for step in 0.. {
-        positions = update_positions(positions, &mut blizzards);
-        if positions.contains(&END) {
-            return step + 1;
+#[aoc_generator(day24)]
+fn parse(input: &str) -> Result<Valley, ParseError> {
+    let src = Source::new(24, input);
+    let rows: Vec<&str> = input.lines().collect();
+    if rows.len() < 3 {
+        return Err(src.missing("valley with walls above and below"));
+    }
+    let map = Grid::parse(&src, input, "#, ., <, ^, > or v", |c| {
+        "#.<^>v".contains(c).then_some(c)
+    })?;
+    if map.width() < 3 {
+        return Err(src.error(rows[0], "valley with walls either side"));
+    }
+    // blizzards wrap around inside the walls, so they have to go all the way
+    // round, with one gap each in the top and bottom
+    let last = map.width() - 1;
+    for (r, row) in rows.iter().enumerate().skip(1).take(map.height() - 2) {
+        for c in [0, last] {
+            if map[(r, c)] != '#' {
+                return Err(src.char_error(row, c, "#"));
+            }
         }
     }
-    0
+    let gap = |r: usize| {
+        let row = map.row(r);
+        if let Some(c) = row.iter().position(|&c| !"#.".contains(c)) {
+            return Err(src.char_error(rows[r], c, "# or ."));
+        }
+        let gaps: Vec<usize> = (1..last).filter(|&c| row[c] == '.').collect();
+        match (row[0], row[last], gaps.as_slice()) {
+            ('#', '#', &[c]) => Ok((r, c)),
+            _ => Err(src.error(rows[r], "wall with one gap")),
+        }
+    };
+    let (width, height) = (map.width() - 2, map.height() - 2);
+    Ok(Valley {
+        entrance: gap(0)?,
+        exit: gap(map.height() - 1)?,
+        walls: map.map(|&c| c == '#'),
+        blizzards: map.map(|&c| if c == '#' { '.' } else { c }),
+        period: width / gcd(width, height) * height,
+    })
+}
+#[aoc(day24, part1)]
+fn part1(input: &Valley) -> usize {
+    input.crossing(input.entrance, input.exit, 0)
 }
 #[aoc(day24, part2)]
-fn part2(input: &[(usize, usize, char)]) -> usize {
-    let mut blizzards = input.to_vec();
-    let mut positions: Hashset<(usize, usize)> = Hashset::from_iter([(0, 1)]);
-    let mut legs = 0;
+fn part2(input: &Valley) -> usize {
+    let (start, end) = (input.entrance, input.exit);
+    let there = input.crossing(start, end, 0);
+    let back = input.crossing(end, start, there);
+    there + back + input.crossing(start, end, there + back)
+}
 
-    for step in 0.. {
-        positions = update_positions(positions, &mut blizzards);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
 
-        match legs {
-            0 => {
-                if positions.contains(&END) {
-                    legs += 1;
-                    positions = HashSet::from_iter([END]);
-                }
-            }
-            1 => {
-                if positions.contains(&START) {
-                    legs += 1;
-                    positions = HashSet::from_iter([START]);
-                }
-            }
-            2 => {
-                if positions.contains(&END) {
-                    return step + 1;
-                }
-            }
-            _ => unreachable!(),
-        }
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(24)).unwrap();
+        assert_eq!(part1(&input), 18);
+        assert_eq!(part2(&input), 54);
+    }
+    #[test]
+    fn walls_all_round() {
+        let error = parse("#.###\n>...#\n#...#\n###.#").unwrap_err();
+        assert_eq!(
+            (error.line, error.column, error.found.as_str()),
+            (2, 1, ">")
+        );
+        let error = parse("#.###\n#...#\n#..v.\n###.#").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 5));
+        let error = parse("#..##\n#...#\n###.#").unwrap_err();
+        assert_eq!(
+            (error.line, error.expected.as_str()),
+            (1, "wall with one gap")
+        );
+        let error = parse("#.###\n#...#\n#####").unwrap_err();
+        assert_eq!(
+            (error.line, error.expected.as_str()),
+            (3, "wall with one gap")
+        );
+        let error = parse("#.#>#\n#...#\n###.#").unwrap_err();
+        assert_eq!((error.line, error.column), (1, 4));
+        assert!(parse("#.###\n#.>.#\n###.#").is_ok());
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse(&real_input(24)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(24, 1));
+        assert_eq!(part2(&input).to_string(), answer(24, 2));
     }
-    0
 }
@@ -1,16 +1,17 @@
+use crate::error::{ParseError, Source};
 use std::iter::once;
 const DIGITS: [u8; 5] = [b'=', b'-', b'0', b'1', b'2'];
-fn from_snafu(snafu: &str) -> i64 {
-    snafu.chars().fold(0, |acc, elem| {
-        acc * 5
+fn from_snafu(src: &Source, snafu: &str) -> Result<i64, ParseError> {
+    snafu.char_indices().try_fold(0, |acc, (i, elem)| {
+        Ok(acc * 5
             + match elem {
                 '=' => -2,
                 '-' => -1,
                 '0' => 0,
                 '1' => 1,
                 '2' => 2,
-                _ => unreachable!(),
-            }
+                _ => return Err(src.char_error(snafu, i, "SNAFU digit")),
+            })
     })
 }
 fn to_snafu(val: i64) -> Vec<u8> {
@@ -28,10 +29,28 @@ fn to_snafu(val: i64) -> Vec<u8> {
     Vec::new()
 }
 #[aoc_generator(day25)]
-fn parse(input: &str) -> Vec<i64> {
-    input.lines().map(from_snafu).collect()
+fn parse(input: &str) -> Result<Vec<i64>, ParseError> {
+    let src = Source::new(25, input);
+    input.lines().map(|l| from_snafu(&src, l)).collect()
 }
 #[aoc(day25, part1)]
 fn part1(input: &[i64]) -> String {
     String::from_utf8(to_snafu(input.iter().sum())).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = parse(&example(25)).unwrap();
+        assert_eq!(part1(&input), "2=-1=0");
+    }
+    #[test]
+    fn real_answers() {
+        let input = parse(&real_input(25)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(25, 1));
+    }
+}
@@ -1,35 +1,54 @@
-use itertools::Itertools;
-
-pub fn priority(c: char) -> u32 {
-    if c.is_lowercase() {
-        c as u32 - 'a' as u32 + 1
-    } else {
-        c as u32 - 'A' as u32 + 27
-    }
-}
-pub fn common_chars(a: &str, b: &str) -> Vec<char> {
-    a.chars().filter(|&c| b.contains(c)).collect()
-}
-#[aoc(day3, part1)]
-pub fn part1(input: &str) -> u32 {
-    input
-        .lines()
-        .map(|l| l.split_at(l.len() / 2))
-        .map(|(r1, r2)| common_chars(r1, r2))
-        .map(|c| priority(c[0]))
-        .sum()
-}
-#[aoc(day3, part2)]
-pub fn part2(input: &str) -> u32 {
-    input
-        .lines()
-        .tuples()
-        .map(|(a, b, c)| {
-            common_chars(
-                a,
-                common_chars(b, c).into_iter().collect::<String>().as_str(),
-            )
-        })
-        .map(|c| priority(c[0]))
-        .sum()
-}
+use itertools::Itertools;
+
+pub fn priority(c: char) -> u32 {
+    if c.is_lowercase() {
+        c as u32 - 'a' as u32 + 1
+    } else {
+        c as u32 - 'A' as u32 + 27
+    }
+}
+pub fn common_chars(a: &str, b: &str) -> Vec<char> {
+    a.chars().filter(|&c| b.contains(c)).collect()
+}
+#[aoc(day3, part1)]
+pub fn part1(input: &str) -> u32 {
+    input
+        .lines()
+        .map(|l| l.split_at(l.len() / 2))
+        .map(|(r1, r2)| common_chars(r1, r2))
+        .map(|c| priority(c[0]))
+        .sum()
+}
+#[aoc(day3, part2)]
+pub fn part2(input: &str) -> u32 {
+    input
+        .lines()
+        .tuples()
+        .map(|(a, b, c)| {
+            common_chars(
+                a,
+                common_chars(b, c).into_iter().collect::<String>().as_str(),
+            )
+        })
+        .map(|c| priority(c[0]))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = example(3);
+        assert_eq!(part1(&input), 157);
+        assert_eq!(part2(&input), 70);
+    }
+    #[test]
+    fn real_answers() {
+        let input = real_input(3);
+        assert_eq!(part1(&input).to_string(), answer(3, 1));
+        assert_eq!(part2(&input).to_string(), answer(3, 2));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::{ParseError, Source};
+
 type Range = (u32, u32);
 // range1 fully contains range2
 pub fn fully_contains(range1: &Range, range2: &Range) -> bool {
@@ -7,16 +9,20 @@ pub fn overlaps(range1: &Range, range2: &Range) -> bool {
     range1.0 <= range2.1 && range2.0 <= range1.1
 }
 #[aoc_generator(day4)]
-pub fn day4_input(input: &str) -> Vec<(Range, Range)> {
+pub fn day4_input(input: &str) -> Result<Vec<(Range, Range)>, ParseError> {
+    let src = Source::new(4, input);
+    let range = |r| -> Result<Range, ParseError> {
+        let (start, end) = src.split_once(r, "-")?;
+        Ok((
+            src.parse(start, "section id")?,
+            src.parse(end, "section id")?,
+        ))
+    };
     input
         .lines()
-        .map(|line| line.split_once(',').unwrap())
-        .map(|(p1, p2)| (p1.split_once('-').unwrap(), p2.split_once('-').unwrap()))
-        .map(|((s1, e1), (s2, e2))| {
-            (
-                (s1.parse().unwrap(), e1.parse().unwrap()),
-                (s2.parse().unwrap(), e2.parse().unwrap()),
-            )
+        .map(|line| {
+            let (p1, p2) = src.split_once(line, ",")?;
+            Ok((range(p1)?, range(p2)?))
         })
         .collect()
 }
@@ -31,3 +37,22 @@ pub fn part1(input: &[(Range, Range)]) -> u32 {
 pub fn part2(input: &[(Range, Range)]) -> u32 {
     input.iter().filter(|(r1, r2)| overlaps(r1, r2)).count() as u32
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day4_input(&example(4)).unwrap();
+        assert_eq!(part1(&input), 2);
+        assert_eq!(part2(&input), 4);
+    }
+    #[test]
+    fn real_answers() {
+        let input = day4_input(&real_input(4)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(4, 1));
+        assert_eq!(part2(&input).to_string(), answer(4, 2));
+    }
+}
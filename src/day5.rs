@@ -1,35 +1,55 @@
+use crate::error::{ParseError, Source};
 use regex::Regex;
 pub struct Input {
     stacks: Vec<Vec<u8>>,
     moves: Vec<(usize, usize, usize)>,
 }
+// crate labels sit every 4 columns, starting at column 1
+fn parse_stacks(src: &Source, drawing: &[&str]) -> Result<Vec<Vec<u8>>, ParseError> {
+    let (labels, rows) = drawing
+        .split_last()
+        .ok_or_else(|| src.missing("stack drawing"))?;
+    let mut stacks = vec![Vec::new(); labels.split_whitespace().count()];
+    // build each stack from the bottom up
+    for row in rows.iter().rev() {
+        for (i, stack) in stacks.iter_mut().enumerate() {
+            match row.as_bytes().get(1 + 4 * i) {
+                Some(&c) if c.is_ascii_alphabetic() => stack.push(c),
+                _ => {}
+            }
+        }
+    }
+    Ok(stacks)
+}
 #[aoc_generator(day5)]
-pub fn day5_input(input: &str) -> Input {
-    let stacks = Vec::from([
-        Vec::from("WRF"),
-        Vec::from("THMCDVWP"),
-        Vec::from("PMZNL"),
-        Vec::from("JCHR"),
-        Vec::from("CPGHQTB"),
-        Vec::from("GCWLFZ"),
-        Vec::from("WVLQZJGC"),
-        Vec::from("PNRFWTVC"),
-        Vec::from("JWHGRSV"),
-    ]);
+pub fn day5_input(input: &str) -> Result<Input, ParseError> {
+    let src = Source::new(5, input);
+    let mut lines = input.lines();
+    // the drawing ends at the first blank line
+    let drawing: Vec<&str> = lines.by_ref().take_while(|l| !l.is_empty()).collect();
+    let stacks = parse_stacks(&src, &drawing)?;
     let re = Regex::new(r"move (\d+) from (\d+) to (\d+)").unwrap();
-    let moves = input
-        .lines()
-        .skip(10)
+    let stack_num = |m: regex::Match| -> Result<usize, ParseError> {
+        let n = src.parse(m.as_str(), "stack number")?;
+        if (1..=stacks.len()).contains(&n) {
+            Ok(n)
+        } else {
+            Err(src.error(m.as_str(), &format!("stack number 1-{}", stacks.len())))
+        }
+    };
+    let moves = lines
         .map(|line| {
-            let caps = re.captures(line).unwrap();
-            (
-                caps[1].parse().unwrap(),
-                caps[2].parse().unwrap(),
-                caps[3].parse().unwrap(),
-            )
+            let caps = re
+                .captures(line)
+                .ok_or_else(|| src.error(line, "move N from A to B"))?;
+            Ok((
+                src.parse(caps.get(1).unwrap().as_str(), "crate count")?,
+                stack_num(caps.get(2).unwrap())?,
+                stack_num(caps.get(3).unwrap())?,
+            ))
         })
-        .collect();
-    Input { stacks, moves }
+        .collect::<Result<_, ParseError>>()?;
+    Ok(Input { stacks, moves })
 }
 #[aoc(day5, part1)]
 pub fn part1(input: &Input) -> String {
@@ -60,3 +80,22 @@ pub fn part2(input: &Input) -> String {
         .map(|val| val as char)
         .collect::<String>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day5_input(&example(5)).unwrap();
+        assert_eq!(part1(&input), "CMZ");
+        assert_eq!(part2(&input), "MCD");
+    }
+    #[test]
+    fn real_answers() {
+        let input = day5_input(&real_input(5)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(5, 1));
+        assert_eq!(part2(&input).to_string(), answer(5, 2));
+    }
+}
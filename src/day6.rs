@@ -2,27 +2,42 @@ use itertools::Itertools;
 use std::collections::HashSet;
 #[aoc(day6, part1)]
 pub fn part1(input: &str) -> usize {
-    let mut count = 4;
-    for (a, b, c, d) in input.chars().tuple_windows() {
+    for (count, (a, b, c, d)) in input.chars().tuple_windows().enumerate() {
         if HashSet::from([a, b, c, d]).len() == 4 {
-            return count;
+            return count + 4;
         }
-        count += 1;
     }
     0
 }
 #[aoc(day6, part2)]
 pub fn part2(input: &str) -> usize {
-    let mut count = 14;
-    for a in input.as_bytes().windows(14) {
+    for (count, a) in input.as_bytes().windows(14).enumerate() {
         let mut s = HashSet::new();
         for &x in a {
             s.insert(x);
         }
         if s.len() == 14 {
-            return count;
+            return count + 14;
         }
-        count += 1;
     }
     0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = example(6);
+        assert_eq!(part1(&input), 7);
+        assert_eq!(part2(&input), 19);
+    }
+    #[test]
+    fn real_answers() {
+        let input = real_input(6);
+        assert_eq!(part1(&input).to_string(), answer(6, 1));
+        assert_eq!(part2(&input).to_string(), answer(6, 2));
+    }
+}
@@ -1,3 +1,5 @@
+use crate::error::{ParseError, Source};
+
 #[derive(Debug)]
 pub struct Directory {
     name: String,
@@ -36,42 +38,53 @@ pub fn compute_total_size(dir: &mut Directory) -> usize {
     dir.total_size
 }
 #[aoc_generator(day7)]
-pub fn day7_input(input: &str) -> Directory {
+pub fn day7_input(input: &str) -> Result<Directory, ParseError> {
+    let src = Source::new(7, input);
     let mut root = Directory::new("".to_string());
 
     let mut pwd = Vec::new();
     // split on $ giving us each instruction (and its outputs)
     for cmd in input.split('$').skip(1) {
-        match cmd.trim().lines().next().unwrap() {
-            "ls" => cmd.lines().skip(1).for_each(|res| {
-                match res.split_once(' ').unwrap() {
-                    // add all sub directories
-                    ("dir", dir_name) => {
-                        path_operation(&mut root, &pwd, |node: &mut Directory| {
-                            node.dirs.push(Directory::new(dir_name.to_string()))
-                        });
-                    }
-                    // add all files
-                    (size, _) => {
-                        path_operation(&mut root, &pwd, |node: &mut Directory| {
-                            node.files.push(size.parse::<usize>().unwrap())
-                        });
-                    }
-                };
-                // apply operation to current directory
-            }),
+        let command = cmd
+            .trim()
+            .lines()
+            .next()
+            .ok_or_else(|| src.error(cmd, "command"))?;
+        match command {
+            "ls" => {
+                for res in cmd.lines().skip(1) {
+                    match src.split_once(res, " ")? {
+                        // add all sub directories
+                        ("dir", dir_name) => {
+                            path_operation(&mut root, &pwd, |node: &mut Directory| {
+                                node.dirs.push(Directory::new(dir_name.to_string()))
+                            });
+                        }
+                        // add all files
+                        (size, _) => {
+                            let size = src.parse::<usize>(size, "file size")?;
+                            path_operation(&mut root, &pwd, |node: &mut Directory| {
+                                node.files.push(size)
+                            });
+                        }
+                    };
+                }
+            }
             // go up one directory
             "cd .." => {
                 pwd.pop();
             }
             "cd /" => {}
             // go to directory
-            cd_dir => pwd.push(cd_dir.split_once(' ').unwrap().1.to_string()),
+            cd_dir => match cd_dir.split_once(' ') {
+                Some(("cd", dir)) => pwd.push(dir.to_string()),
+                _ => return Err(src.error(cd_dir, "ls or cd")),
+            },
         }
     }
     // compute total sizes for each directory
     compute_total_size(&mut root);
-    root
+    Ok(root)
 }
 // helper for below function
 pub fn totals_helper(dir: &Directory, vec: &mut Vec<usize>) {
@@ -102,3 +115,22 @@ pub fn part2(input: &Directory) -> usize {
         .min()
         .unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day7_input(&example(7)).unwrap();
+        assert_eq!(part1(&input), 95437);
+        assert_eq!(part2(&input), 24933642);
+    }
+    #[test]
+    fn real_answers() {
+        let input = day7_input(&real_input(7)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(7, 1));
+        assert_eq!(part2(&input).to_string(), answer(7, 2));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::day9::Direction::*;
+use crate::error::{ParseError, Source};
 use std::collections::HashSet;
 #[derive(Debug)]
 enum Direction {
@@ -33,7 +34,7 @@ impl Rope {
         }
     }
     fn step(&mut self, dir: &Direction) {
-        let mut head = self.knots.first_mut().unwrap();
+        let head = self.knots.first_mut().unwrap();
 
         match dir {
             Left => head.0 -= 1,
@@ -45,20 +46,22 @@ impl Rope {
     }
 }
 #[aoc_generator(day9)]
-fn day9_input(input: &str) -> Vec<Move> {
+fn day9_input(input: &str) -> Result<Vec<Move>, ParseError> {
+    let src = Source::new(9, input);
     input
         .lines()
         .map(|line| {
-            let (dir, len) = line.split_once(' ').unwrap();
-            Move {
+            let (dir, len) = src.split_once(line, " ")?;
+            Ok(Move {
                 dir: match dir {
                     "L" => Left,
                     "R" => Right,
                     "U" => Up,
-                    _ => Down,
+                    "D" => Down,
+                    _ => return Err(src.error(dir, "L, R, U or D")),
                 },
-                num_steps: len.parse().unwrap(),
-            }
+                num_steps: src.parse(len, "step count")?,
+            })
         })
         .collect()
 }
@@ -92,3 +95,22 @@ fn part2(input: &[Move]) -> usize {
     }
     set.len()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{answer, example, real_input};
+
+    #[test]
+    fn example_answers() {
+        let input = day9_input(&example(9)).unwrap();
+        assert_eq!(part1(&input), 88);
+        assert_eq!(part2(&input), 36);
+    }
+    #[test]
+    fn real_answers() {
+        let input = day9_input(&real_input(9)).unwrap();
+        assert_eq!(part1(&input).to_string(), answer(9, 1));
+        assert_eq!(part2(&input).to_string(), answer(9, 2));
+    }
+}
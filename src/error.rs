@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+// a malformed puzzle input, pointing at where parsing went wrong
+// line and column are both 1-based, column counts chars
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u32,
+    // what was being read, when it isn't the day's input
+    pub file: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "day {} {file}", self.day)?,
+            None => write!(f, "day {} input", self.day)?,
+        }
+        write!(
+            f,
+            ", line {}, column {}: expected {}, found {:?}",
+            self.line, self.column, self.expected, self.found
+        )
+    }
+}
+impl Error for ParseError {}
+
+// the whole input for a day, so errors can be located from any slice of it
+#[derive(Debug, Copy, Clone)]
+pub struct Source<'a> {
+    day: u32,
+    file: Option<&'a str>,
+    input: &'a str,
+}
+impl<'a> Source<'a> {
+    pub fn new(day: u32, input: &'a str) -> Self {
+        Source {
+            day,
+            file: None,
+            input,
+        }
+    }
+    // some other text for the day than its input, like a file it was given
+    pub fn file(day: u32, file: &'a str, input: &'a str) -> Self {
+        Source {
+            day,
+            file: Some(file),
+            input,
+        }
+    }
+    // `token` has to be a slice of the input, its position is worked out
+    // from where it sits in memory
+    pub fn error(&self, token: &str, expected: &str) -> ParseError {
+        let start = self.input.as_ptr() as usize;
+        let offset = (token.as_ptr() as usize).wrapping_sub(start);
+        debug_assert!(offset <= self.input.len(), "token is not part of the input");
+        let offset = offset.min(self.input.len());
+
+        let before = &self.input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseError {
+            day: self.day,
+            file: self.file.map(str::to_string),
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected: expected.to_string(),
+            found: token.to_string(),
+        }
+    }
+    // just the char starting at byte `index` of `s`
+    pub fn char_error(&self, s: &str, index: usize, expected: &str) -> ParseError {
+        let len = s[index..].chars().next().map_or(0, char::len_utf8);
+        self.error(&s[index..index + len], expected)
+    }
+    // for things that should have been somewhere in the input but weren't
+    pub fn missing(&self, expected: &str) -> ParseError {
+        self.error(&self.input[self.input.len()..], expected)
+    }
+    pub fn parse<T: FromStr>(&self, token: &'a str, expected: &str) -> Result<T, ParseError> {
+        token.parse().map_err(|_| self.error(token, expected))
+    }
+    pub fn split_once(
+        &self,
+        token: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), ParseError> {
+        token
+            .split_once(delimiter)
+            .ok_or_else(|| self.error(token, &format!("{delimiter:?}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locates_token() {
+        let input = "1-2,3-4\n5-6,7-x\n";
+        let src = Source::new(4, input);
+        let line = input.lines().nth(1).unwrap();
+        let err = src.parse::<u32>(&line[6..], "section id").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 7));
+        assert_eq!(err.found, "x");
+        assert_eq!(
+            err.to_string(),
+            r#"day 4 input, line 2, column 7: expected section id, found "x""#
+        );
+    }
+    #[test]
+    fn missing_points_past_the_end() {
+        let src = Source::new(12, "abc\ndef");
+        let err = src.missing("start S");
+        assert_eq!((err.line, err.column), (2, 4));
+    }
+    #[test]
+    fn names_other_files() {
+        let src = Source::file(22, "gluing cube.txt", "1,9 1,12 ^\n");
+        let err = src.missing("=");
+        assert_eq!(err.file.as_deref(), Some("gluing cube.txt"));
+        assert_eq!(
+            err.to_string(),
+            r#"day 22 gluing cube.txt, line 2, column 1: expected =, found """#
+        );
+    }
+}
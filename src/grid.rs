@@ -0,0 +1,273 @@
+use crate::error::{ParseError, Source};
+use fxhash::FxHashMap as HashMap;
+use std::ops::{Index, IndexMut};
+
+// (row, col) into a dense grid
+pub type Pos = (usize, usize);
+// (row, col) into a sparse grid, which can grow in any direction
+pub type Point = (i32, i32);
+
+const ORTHOGONAL: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
+const SURROUNDING: [(i32, i32); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+    // one row per line, mapping each char with `f`
+    // rows shorter than the widest one are padded as if they ended in spaces
+    pub fn parse(
+        src: &Source,
+        input: &str,
+        expected: &str,
+        mut f: impl FnMut(char) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let lines: Vec<&str> = input.lines().collect();
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * lines.len());
+        for line in &lines {
+            for (i, c) in line.char_indices() {
+                cells.push(f(c).ok_or_else(|| src.char_error(line, i, expected))?);
+            }
+            for _ in line.chars().count()..width {
+                let pad = f(' ').ok_or_else(|| src.error(&line[line.len()..], expected))?;
+                cells.push(pad);
+            }
+        }
+        Ok(Grid {
+            width,
+            height: lines.len(),
+            cells,
+        })
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn get(&self, (row, col): Pos) -> Option<&T> {
+        (row < self.height && col < self.width).then(|| &self.cells[row * self.width + col])
+    }
+    pub fn get_mut(&mut self, (row, col): Pos) -> Option<&mut T> {
+        (row < self.height && col < self.width).then(|| &mut self.cells[row * self.width + col])
+    }
+    pub fn row(&self, row: usize) -> &[T] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+    pub fn positions(&self) -> impl Iterator<Item = Pos> {
+        let width = self.width;
+        (0..self.height).flat_map(move |r| (0..width).map(move |c| (r, c)))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+    pub fn position(&self, mut pred: impl FnMut(&T) -> bool) -> Option<Pos> {
+        self.iter().find(|(_, t)| pred(t)).map(|(p, _)| p)
+    }
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+    // step from pos by (drow, dcol), if that stays on the grid
+    pub fn offset(&self, (row, col): Pos, (dr, dc): (i32, i32)) -> Option<Pos> {
+        let row = row.checked_add_signed(dr as isize)?;
+        let col = col.checked_add_signed(dc as isize)?;
+        (row < self.height && col < self.width).then_some((row, col))
+    }
+    // up, right, down, left
+    pub fn neighbors4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        ORTHOGONAL.iter().filter_map(move |&d| self.offset(pos, d))
+    }
+    pub fn neighbors8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        SURROUNDING.iter().filter_map(move |&d| self.offset(pos, d))
+    }
+    pub fn to_sparse<U>(&self, mut f: impl FnMut(&T) -> Option<U>) -> SparseGrid<U> {
+        let mut sparse = SparseGrid::new();
+        for ((row, col), t) in self.iter() {
+            if let Some(u) = f(t) {
+                sparse.insert((row as i32, col as i32), u);
+            }
+        }
+        sparse
+    }
+}
+impl<T: Clone> Grid<T> {
+    pub fn transpose(&self) -> Self {
+        self.remap(self.height, self.width, |(r, c)| (c, r))
+    }
+    // quarter turn clockwise
+    pub fn rotate_cw(&self) -> Self {
+        let height = self.height;
+        self.remap(height, self.width, |(r, c)| (c, height - 1 - r))
+    }
+    // quarter turn counterclockwise
+    pub fn rotate_ccw(&self) -> Self {
+        let width = self.width;
+        self.remap(self.height, width, |(r, c)| (width - 1 - c, r))
+    }
+    // new grid where the cell at pos ends up at to(pos)
+    fn remap(&self, width: usize, height: usize, to: impl Fn(Pos) -> Pos) -> Self {
+        let mut cells = self.cells.clone();
+        for (pos, t) in self.iter() {
+            let (r, c) = to(pos);
+            cells[r * width + c] = t.clone();
+        }
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+}
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, pos: Pos) -> &Self::Output {
+        self.get(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is off the {}x{} grid", self.height, self.width))
+    }
+}
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, pos: Pos) -> &mut Self::Output {
+        let (height, width) = (self.height, self.width);
+        self.get_mut(pos)
+            .unwrap_or_else(|| panic!("{pos:?} is off the {height}x{width} grid"))
+    }
+}
+
+// only the occupied cells, for things with no fixed bounds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+    pub fn with_capacity(capacity: usize) -> Self {
+        SparseGrid {
+            cells: HashMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+    pub fn capacity(&self) -> usize {
+        self.cells.capacity()
+    }
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.cells.get(&point)
+    }
+    pub fn contains(&self, point: Point) -> bool {
+        self.cells.contains_key(&point)
+    }
+    pub fn insert(&mut self, point: Point, t: T) -> Option<T> {
+        self.cells.insert(point, t)
+    }
+    pub fn remove(&mut self, point: Point) -> Option<T> {
+        self.cells.remove(&point)
+    }
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells.keys().copied()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&p, t)| (p, t))
+    }
+    // smallest and largest (row, col) of anything in the grid
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let mut points = self.points();
+        let first = points.next()?;
+        Some(points.fold((first, first), |(min, max), (r, c)| {
+            ((min.0.min(r), min.1.min(c)), (max.0.max(r), max.1.max(c)))
+        }))
+    }
+    // up, right, down, left
+    pub fn neighbors4(&self, (row, col): Point) -> impl Iterator<Item = Point> {
+        ORTHOGONAL.iter().map(move |&(dr, dc)| (row + dr, col + dc))
+    }
+    pub fn neighbors8(&self, (row, col): Point) -> impl Iterator<Item = Point> {
+        SURROUNDING
+            .iter()
+            .map(move |&(dr, dc)| (row + dr, col + dc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn letters() -> Grid<char> {
+        let input = "abc\ndef";
+        Grid::parse(&Source::new(0, input), input, "letter", Some).unwrap()
+    }
+    #[test]
+    fn transpose_and_rotate() {
+        let grid = letters();
+        let rows = |g: &Grid<char>| {
+            g.rows()
+                .map(|r| r.iter().collect())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(rows(&grid.transpose()), ["ad", "be", "cf"]);
+        assert_eq!(rows(&grid.rotate_cw()), ["da", "eb", "fc"]);
+        assert_eq!(rows(&grid.rotate_ccw()), ["cf", "be", "ad"]);
+        assert_eq!(grid.rotate_cw().rotate_ccw(), grid);
+    }
+    #[test]
+    fn neighbors_stay_in_bounds() {
+        let grid = letters();
+        assert_eq!(
+            grid.neighbors4((0, 0)).collect::<Vec<_>>(),
+            [(0, 1), (1, 0)]
+        );
+        assert_eq!(grid.neighbors8((1, 1)).count(), 5);
+        assert_eq!(grid.get((2, 0)), None);
+    }
+    #[test]
+    fn pads_short_rows() {
+        let input = "..#\n.";
+        let src = Source::new(0, input);
+        let grid = Grid::parse(&src, input, "map", |c| Some(c == '#')).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert!(!grid[(1, 2)]);
+        let err = Grid::parse(&src, input, "# or .", |c| (c != ' ').then_some(c)).unwrap_err();
+        assert_eq!((err.line, err.column), (2, 2));
+    }
+}
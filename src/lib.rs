@@ -10,6 +10,7 @@ pub mod day14;
 pub mod day15;
 pub mod day16;
 pub mod day17;
+pub mod day18;
 pub mod day19;
 pub mod day2;
 pub mod day20;
@@ -25,4 +26,9 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod error;
+pub mod grid;
+pub mod search;
+#[cfg(test)]
+mod test_util;
 aoc_lib! { year = 2022 }
@@ -0,0 +1,204 @@
+use fxhash::FxHashMap as HashMap;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::hash::Hash;
+
+// anything we can walk around in
+pub trait Graph {
+    type Node: Clone + Eq + Hash;
+
+    // nodes one step away from `node`, along with what the step costs
+    // bfs ignores the costs and counts every step as 1
+    fn neighbors(&self, node: &Self::Node) -> impl Iterator<Item = (Self::Node, usize)>;
+}
+
+// a route through the graph, both ends included
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<N> {
+    pub cost: usize,
+    pub nodes: Vec<N>,
+}
+impl<N> Path<N> {
+    pub fn start(&self) -> &N {
+        &self.nodes[0]
+    }
+    pub fn end(&self) -> &N {
+        &self.nodes[self.nodes.len() - 1]
+    }
+}
+
+// everything a search reached, with how far away it was and how we got there
+#[derive(Debug, Clone)]
+pub struct SearchTree<N: Eq + Hash> {
+    dist: HashMap<N, usize>,
+    parent: HashMap<N, N>,
+}
+impl<N: Clone + Eq + Hash> SearchTree<N> {
+    fn new() -> Self {
+        SearchTree {
+            dist: HashMap::default(),
+            parent: HashMap::default(),
+        }
+    }
+    pub fn distance(&self, node: &N) -> Option<usize> {
+        self.dist.get(node).copied()
+    }
+    pub fn distances(&self) -> impl Iterator<Item = (&N, usize)> {
+        self.dist.iter().map(|(n, &d)| (n, d))
+    }
+    pub fn path_to(&self, node: &N) -> Option<Path<N>> {
+        let cost = self.distance(node)?;
+        let mut nodes = vec![node.clone()];
+        while let Some(prev) = self.parent.get(nodes.last().unwrap()) {
+            nodes.push(prev.clone());
+        }
+        nodes.reverse();
+        Some(Path { cost, nodes })
+    }
+}
+
+// fewest steps from any of `starts` to the first node that is_goal
+pub fn bfs<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<Path<G::Node>> {
+    let (tree, goal) = bfs_until(graph, starts, is_goal);
+    tree.path_to(&goal?)
+}
+// step counts from `starts` to everything reachable
+pub fn bfs_all<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+) -> SearchTree<G::Node> {
+    bfs_until(graph, starts, |_| false).0
+}
+fn bfs_until<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> (SearchTree<G::Node>, Option<G::Node>) {
+    let mut tree = SearchTree::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if tree.dist.insert(start.clone(), 0).is_none() {
+            queue.push_back((start, 0));
+        }
+    }
+    while let Some((node, len)) = queue.pop_front() {
+        if is_goal(&node) {
+            return (tree, Some(node));
+        }
+        for (next, _) in graph.neighbors(&node) {
+            if !tree.dist.contains_key(&next) {
+                tree.dist.insert(next.clone(), len + 1);
+                tree.parent.insert(next.clone(), node.clone());
+                queue.push_back((next, len + 1));
+            }
+        }
+    }
+    (tree, None)
+}
+
+// cheapest route from any of `starts` to the first node that is_goal
+pub fn dijkstra<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+) -> Option<Path<G::Node>> {
+    astar(graph, starts, is_goal, |_| 0)
+}
+// cheapest costs from `starts` to everything reachable
+pub fn dijkstra_all<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+) -> SearchTree<G::Node> {
+    astar_until(graph, starts, |_| false, |_| 0).0
+}
+// dijkstra guided by `heuristic`, which must never overestimate the
+// remaining cost or the path might not be the cheapest
+pub fn astar<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+    heuristic: impl Fn(&G::Node) -> usize,
+) -> Option<Path<G::Node>> {
+    let (tree, goal) = astar_until(graph, starts, is_goal, heuristic);
+    tree.path_to(&goal?)
+}
+fn astar_until<G: Graph>(
+    graph: &G,
+    starts: impl IntoIterator<Item = G::Node>,
+    is_goal: impl Fn(&G::Node) -> bool,
+    heuristic: impl Fn(&G::Node) -> usize,
+) -> (SearchTree<G::Node>, Option<G::Node>) {
+    let mut tree = SearchTree::new();
+    // the heap holds indices into `nodes` so nodes don't need to be Ord
+    let mut nodes = Vec::new();
+    let mut heap = BinaryHeap::new();
+    for start in starts {
+        if tree.dist.insert(start.clone(), 0).is_none() {
+            heap.push(Reverse((heuristic(&start), 0, nodes.len())));
+            nodes.push(start);
+        }
+    }
+    while let Some(Reverse((_, cost, idx))) = heap.pop() {
+        let node = nodes[idx].clone();
+        // stale entry, we've since found a cheaper way here
+        if tree.dist[&node] < cost {
+            continue;
+        }
+        if is_goal(&node) {
+            return (tree, Some(node));
+        }
+        for (next, step) in graph.neighbors(&node) {
+            let new_cost = cost + step;
+            if tree.dist.get(&next).is_none_or(|&d| new_cost < d) {
+                tree.dist.insert(next.clone(), new_cost);
+                tree.parent.insert(next.clone(), node.clone());
+                heap.push(Reverse((
+                    new_cost + heuristic(&next),
+                    new_cost,
+                    nodes.len(),
+                )));
+                nodes.push(next);
+            }
+        }
+    }
+    (tree, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a line of nodes 0..10 plus a pricey shortcut from 0 to 9
+    struct Line;
+    impl Graph for Line {
+        type Node = u32;
+
+        fn neighbors(&self, &n: &u32) -> impl Iterator<Item = (u32, usize)> {
+            let shortcut = (n == 0).then_some((9, 20));
+            [n.checked_sub(1), (n < 9).then_some(n + 1)]
+                .into_iter()
+                .flatten()
+                .map(|m| (m, 1))
+                .chain(shortcut)
+        }
+    }
+    #[test]
+    fn bfs_counts_steps() {
+        let path = bfs(&Line, [0], |&n| n == 9).unwrap();
+        assert_eq!((path.cost, path.nodes), (1, vec![0, 9]));
+        assert_eq!(bfs_all(&Line, [5]).distance(&0), Some(5));
+    }
+    #[test]
+    fn dijkstra_and_astar_count_cost() {
+        let path = dijkstra(&Line, [0], |&n| n == 9).unwrap();
+        assert_eq!(path.cost, 9);
+        assert_eq!(path.nodes, (0..=9).collect::<Vec<_>>());
+        let path = astar(&Line, [0], |&n| n == 9, |&n| 9 - n as usize).unwrap();
+        assert_eq!(path.cost, 9);
+        assert_eq!(dijkstra_all(&Line, [0]).distance(&9), Some(9));
+    }
+}
@@ -0,0 +1,28 @@
+use std::fs;
+
+fn read(name: &str) -> String {
+    let path = format!("{}/input/2022/{name}", env!("CARGO_MANIFEST_DIR"));
+    let input = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{path}: {e}"));
+    // same trimming aoc-runner does before handing the input over
+    input.trim_end_matches('\n').to_string()
+}
+// the puzzle example, input/2022/exampleN.txt
+pub fn example(day: u32) -> String {
+    read(&format!("example{day}.txt"))
+}
+// our real input, input/2022/dayN.txt
+pub fn real_input(day: u32) -> String {
+    read(&format!("day{day}.txt"))
+}
+// recorded answer for the real input, from input/2022/answers.txt
+pub fn answer(day: u32, part: u32) -> String {
+    read("answers.txt")
+        .lines()
+        .filter(|l| !l.starts_with('#'))
+        .find_map(|l| {
+            let mut fields = l.splitn(3, ' ');
+            let key = (fields.next()?.parse().ok()?, fields.next()?.parse().ok()?);
+            (key == (day, part)).then(|| fields.next().unwrap_or("").to_string())
+        })
+        .unwrap_or_else(|| panic!("no recorded answer for day {day} part {part}"))
+}